@@ -0,0 +1,117 @@
+use soroban_sdk::{contractimpl, contracttype, Address, BytesN, Env};
+
+use crate::storage::{self, DataKey};
+use crate::{events, load_mission, upgrade, Error, MissionFundContract, MissionFundContractArgs, MissionFundContractClient};
+
+/// Platform roles the admin can assign besides itself.
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Role {
+    /// Reviews milestones of missions registered without their own reviewer.
+    Reviewer,
+    /// Receives platform fees.
+    FeeCollector,
+}
+
+pub(crate) fn admin(env: &Env) -> Result<Address, Error> {
+    env.storage()
+        .instance()
+        .get(&DataKey::Admin)
+        .ok_or(Error::NotInitialized)
+}
+
+pub(crate) fn require_admin(env: &Env) -> Result<Address, Error> {
+    let admin = admin(env)?;
+    admin.require_auth();
+    Ok(admin)
+}
+
+pub(crate) fn role(env: &Env, role: Role) -> Option<Address> {
+    env.storage().instance().get(&DataKey::Role(role))
+}
+
+/// Fails if the whole contract or this mission is paused.
+pub(crate) fn ensure_live(env: &Env, mission_id: &BytesN<32>) -> Result<(), Error> {
+    if env.storage().instance().get(&DataKey::Paused).unwrap_or(false) {
+        return Err(Error::Paused);
+    }
+    if storage::read(env, &DataKey::MissionPaused(mission_id.clone())).unwrap_or(false) {
+        return Err(Error::MissionPaused);
+    }
+    Ok(())
+}
+
+#[contractimpl]
+impl MissionFundContract {
+    pub fn get_admin(env: Env) -> Result<Address, Error> {
+        admin(&env)
+    }
+
+    /// First step of an admin handover. The proposal only takes effect once
+    /// `new_admin` calls `accept_admin`; proposing again replaces it.
+    pub fn propose_admin(env: Env, new_admin: Address) -> Result<(), Error> {
+        let admin = require_admin(&env)?;
+        env.storage().instance().set(&DataKey::PendingAdmin, &new_admin);
+        events::admin_proposed(&env, &admin, &new_admin);
+        Ok(())
+    }
+
+    pub fn accept_admin(env: Env) -> Result<(), Error> {
+        let pending: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::PendingAdmin)
+            .ok_or(Error::NoPendingAdmin)?;
+        pending.require_auth();
+        env.storage().instance().set(&DataKey::Admin, &pending);
+        env.storage().instance().remove(&DataKey::PendingAdmin);
+        events::admin_accepted(&env, &pending);
+        Ok(())
+    }
+
+    pub fn get_pending_admin(env: Env) -> Option<Address> {
+        env.storage().instance().get(&DataKey::PendingAdmin)
+    }
+
+    /// Assigns `role` to `holder`, or revokes it when `holder` is `None`.
+    pub fn set_role(env: Env, role: Role, holder: Option<Address>) -> Result<(), Error> {
+        require_admin(&env)?;
+        match &holder {
+            Some(holder) => env.storage().instance().set(&DataKey::Role(role), holder),
+            None => env.storage().instance().remove(&DataKey::Role(role)),
+        }
+        events::role_set(&env, role, &holder);
+        Ok(())
+    }
+
+    pub fn get_role(env: Env, role: Role) -> Option<Address> {
+        self::role(&env, role)
+    }
+
+    /// Emergency stop for every mission: blocks funding, withdrawals and
+    /// milestone payouts. Refunds stay open so donors can always get out.
+    pub fn set_paused(env: Env, paused: bool) -> Result<(), Error> {
+        require_admin(&env)?;
+        env.storage().instance().set(&DataKey::Paused, &paused);
+        events::paused(&env, paused);
+        Ok(())
+    }
+
+    pub fn is_paused(env: Env) -> bool {
+        env.storage().instance().get(&DataKey::Paused).unwrap_or(false)
+    }
+
+    /// Same as `set_paused` for a single mission.
+    pub fn set_mission_paused(env: Env, mission_id: BytesN<32>, paused: bool) -> Result<(), Error> {
+        upgrade::ensure_current(&env)?;
+        require_admin(&env)?;
+        load_mission(&env, &mission_id)?;
+        storage::write(&env, &DataKey::MissionPaused(mission_id.clone()), &paused);
+        events::mission_paused(&env, &mission_id, paused);
+        Ok(())
+    }
+
+    pub fn is_mission_paused(env: Env, mission_id: BytesN<32>) -> bool {
+        storage::read(&env, &DataKey::MissionPaused(mission_id)).unwrap_or(false)
+    }
+}
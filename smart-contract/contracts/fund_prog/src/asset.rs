@@ -0,0 +1,199 @@
+use soroban_sdk::{contractimpl, contracttype, token, Address, BytesN, Env, Map, Vec};
+
+use crate::storage::DataKey;
+use crate::{admin, contribution, events, load_mission, math, save_mission, team, upgrade, Error, Mission, MissionFundContract, MissionFundContractArgs, MissionFundContractClient};
+
+/// Fixed-point scale of conversion rates: a rate of `RATE_SCALE` means one
+/// base unit of the asset is worth one base unit of the platform token.
+pub const RATE_SCALE: i128 = 10_000_000;
+
+/// Upper bound on assets a single mission accepts.
+pub const MAX_MISSION_ASSETS: u32 = 5;
+
+/// A mission's holdings in one asset. `raised` is what donors have put in
+/// and not taken back through refunds; `held` is what the contract still
+/// holds for the mission after payouts to the owner.
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct AssetFunds {
+    pub raised: i128,
+    pub held: i128,
+}
+
+/// The Stellar Asset Contract set at `initialize`. It is always supported
+/// and every conversion rate is expressed in it.
+pub(crate) fn platform_token(env: &Env) -> Result<Address, Error> {
+    env.storage()
+        .instance()
+        .get(&DataKey::Token)
+        .ok_or(Error::NotInitialized)
+}
+
+fn rates(env: &Env) -> Map<Address, i128> {
+    env.storage()
+        .instance()
+        .get(&DataKey::Rates)
+        .unwrap_or(Map::new(env))
+}
+
+fn rate(env: &Env, asset: &Address) -> Result<i128, Error> {
+    if *asset == platform_token(env)? {
+        return Ok(RATE_SCALE);
+    }
+    rates(env).get(asset.clone()).ok_or(Error::UnsupportedAsset)
+}
+
+/// Values `amount` of `asset` in units of `reference` at the current rates.
+pub(crate) fn to_reference(
+    env: &Env,
+    amount: i128,
+    asset: &Address,
+    reference: &Address,
+) -> Result<i128, Error> {
+    if asset == reference {
+        return Ok(amount);
+    }
+    math::mul_div(amount, rate(env, asset)?, rate(env, reference)?)
+}
+
+/// The holdings of a newly registered mission: the platform token only.
+pub(crate) fn initial_funds(env: &Env, token: &Address) -> Map<Address, AssetFunds> {
+    let mut funds = Map::new(env);
+    funds.set(token.clone(), AssetFunds { raised: 0, held: 0 });
+    funds
+}
+
+/// Pays `to` the fraction `part / whole` of every asset the mission holds,
+/// or all of it once `part` reaches `whole`.
+pub(crate) fn pay_out(
+    env: &Env,
+    mission: &mut Mission,
+    part: i128,
+    whole: i128,
+    to: &Address,
+) -> Result<(), Error> {
+    for asset in mission.funds.keys().iter() {
+        let mut funds = mission.funds.get_unchecked(asset.clone());
+        let amount = if part >= whole {
+            funds.held
+        } else {
+            math::mul_div(funds.held, part, whole)?
+        };
+        if amount > 0 {
+            token::Client::new(env, &asset).transfer(&env.current_contract_address(), to, &amount);
+            funds.held -= amount;
+            mission.funds.set(asset, funds);
+        }
+    }
+    Ok(())
+}
+
+/// Returns a donor's per-asset contributions to the mission. Each asset's
+/// refund is their share of what the mission still holds in it, which is the
+/// full amount unless the owner was already paid.
+pub(crate) fn refund(
+    env: &Env,
+    mission: &mut Mission,
+    contributed: &Map<Address, i128>,
+    donor: &Address,
+) -> Result<(), Error> {
+    for (asset, amount) in contributed.iter() {
+        let Some(mut funds) = mission.funds.get(asset.clone()) else {
+            continue;
+        };
+        if amount == 0 || funds.raised == 0 {
+            continue;
+        }
+        // Flooring each share only ever leaves more for later claims, and the
+        // last donor's share is whatever remains.
+        let payout = math::mul_div(amount, funds.held, funds.raised)?;
+        funds.raised -= amount;
+        funds.held -= payout;
+        mission.funds.set(asset.clone(), funds);
+        if payout > 0 {
+            token::Client::new(env, &asset).transfer(&env.current_contract_address(), donor, &payout);
+        }
+    }
+    Ok(())
+}
+
+/// Sets the assets a mission accepts; see `set_mission_assets`.
+pub(crate) fn set_assets(
+    env: &Env,
+    mission_id: &BytesN<32>,
+    reference: Address,
+    accepted: Vec<Address>,
+) -> Result<(), Error> {
+    let mut mission = load_mission(env, mission_id)?;
+    mission.ensure_not_cancelled()?;
+    if contribution::donor_count(env, mission_id) > 0 {
+        return Err(Error::AssetsLocked);
+    }
+    if accepted.is_empty()
+        || accepted.len() > MAX_MISSION_ASSETS
+        || !accepted.contains(&reference)
+    {
+        return Err(Error::InvalidConfig);
+    }
+
+    let mut funds = Map::new(env);
+    for asset in accepted.iter() {
+        rate(env, &asset)?;
+        if funds.contains_key(asset.clone()) {
+            return Err(Error::InvalidConfig);
+        }
+        funds.set(asset, AssetFunds { raised: 0, held: 0 });
+    }
+    mission.reference_asset = reference;
+    mission.funds = funds;
+    save_mission(env, mission_id, &mission);
+    events::mission_assets_set(env, mission_id, &mission.reference_asset, &accepted);
+    Ok(())
+}
+
+#[contractimpl]
+impl MissionFundContract {
+    /// Assets missions may accept besides the platform token, with their
+    /// rates.
+    pub fn get_asset_rates(env: Env) -> Map<Address, i128> {
+        rates(&env)
+    }
+
+    /// Admin sets the value of one base unit of `asset` in platform token
+    /// units, scaled by `RATE_SCALE`, which also whitelists it. `None`
+    /// removes the asset: missions keep what they hold in it but can no
+    /// longer take donations in it.
+    pub fn set_asset_rate(env: Env, asset: Address, rate: Option<i128>) -> Result<(), Error> {
+        admin::require_admin(&env)?;
+        if asset == platform_token(&env)? || rate.is_some_and(|rate| rate <= 0) {
+            return Err(Error::InvalidConfig);
+        }
+        let mut rates = rates(&env);
+        match rate {
+            Some(rate) => rates.set(asset.clone(), rate),
+            None => {
+                rates.remove(asset.clone());
+            }
+        }
+        env.storage().instance().set(&DataKey::Rates, &rates);
+        events::rate_set(&env, &asset, rate);
+        Ok(())
+    }
+
+    /// Owner chooses the assets a mission accepts and the `reference` asset
+    /// its target is expressed in, which must be one of them. Only possible
+    /// before the first donation. Team-owned missions choose through a
+    /// `TeamAction::SetAssets` proposal.
+    pub fn set_mission_assets(
+        env: Env,
+        mission_id: BytesN<32>,
+        reference: Address,
+        accepted: Vec<Address>,
+    ) -> Result<(), Error> {
+        upgrade::ensure_current(&env)?;
+        let mission = load_mission(&env, &mission_id)?;
+        mission.owner.require_auth();
+        team::ensure_solo(&env, &mission_id)?;
+        set_assets(&env, &mission_id, reference, accepted)
+    }
+}
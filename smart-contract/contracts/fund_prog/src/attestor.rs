@@ -0,0 +1,37 @@
+use soroban_sdk::{contractclient, contractimpl, Address, BytesN, Env};
+
+use crate::storage::{self, DataKey};
+use crate::{Error, MissionFundContract, MissionFundContractArgs, MissionFundContractClient};
+
+/// Interface a compliance partner's contract implements so missions can
+/// restrict who may donate.
+#[contractclient(name = "AttestorClient")]
+pub trait Attestor {
+    /// Whether `donor` has passed the partner's checks.
+    fn is_verified(env: Env, donor: Address) -> bool;
+}
+
+pub(crate) fn attestor(env: &Env, mission_id: &BytesN<32>) -> Option<Address> {
+    storage::read(env, &DataKey::Attestor(mission_id.clone()))
+}
+
+/// Fails unless the mission's attestor, if it has one, vouches for `donor`.
+/// An attestor call that fails counts as a refusal.
+pub(crate) fn ensure_verified(env: &Env, mission_id: &BytesN<32>, donor: &Address) -> Result<(), Error> {
+    let Some(attestor) = attestor(env, mission_id) else {
+        return Ok(());
+    };
+    match AttestorClient::new(env, &attestor).try_is_verified(donor) {
+        Ok(Ok(true)) => Ok(()),
+        _ => Err(Error::DonorNotVerified),
+    }
+}
+
+#[contractimpl]
+impl MissionFundContract {
+    /// The contract vetting donors to a mission, if it was registered with
+    /// one.
+    pub fn get_mission_attestor(env: Env, mission_id: BytesN<32>) -> Option<Address> {
+        attestor(&env, &mission_id)
+    }
+}
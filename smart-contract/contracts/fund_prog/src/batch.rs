@@ -0,0 +1,131 @@
+use soroban_sdk::{contractimpl, token, Address, BytesN, Env, Map, Vec};
+
+use crate::storage::{self, DataKey};
+use crate::{accept_donation, callback, contribution, fee, load_mission, matching, math, upgrade, Error, Mission, MissionFundContract, MissionFundContractArgs, MissionFundContractClient, Payment};
+
+/// Network limit on ledger entries written by one transaction.
+const TX_WRITE_LIMIT: u32 = 25;
+
+/// Entries a batch writes once whatever its size: the contract instance
+/// (token counter), the nonce of the donor's signature and their token and
+/// mission counts.
+const SHARED_WRITES: u32 = 4;
+
+/// Entries written per asset paid in: the donor's, the contract's and the
+/// fee collector's balances.
+const ASSET_WRITES: u32 = 3;
+
+/// Entries every mission in a batch writes: the mission, the donor's
+/// contribution, the token and its slot in the donor's token list.
+const MISSION_WRITES: u32 = 4;
+
+/// Extra entries when the donor is new to the mission: their slot in the
+/// mission's donor list, its count and the mission's slot in the donor's
+/// list.
+const NEW_DONOR_WRITES: u32 = 3;
+
+/// Extra entries per matching round the mission is in whose window is
+/// open: the donor's round tally and the mission's round entry.
+const ROUND_WRITES: u32 = 2;
+
+/// Upper bound on missions funded in one `fund_missions` call: as many as
+/// fit the write limit at `MISSION_WRITES` each. Ledger writes run out
+/// first, and most baskets cost more per mission, so each batch is also
+/// priced entry by entry (see `entry_writes`) and refused if its worst
+/// case would not fit.
+pub const MAX_BATCH_FUNDING: u32 = (TX_WRITE_LIMIT - SHARED_WRITES - ASSET_WRITES) / MISSION_WRITES;
+
+/// Most entries funding `mission_id` in a batch could write: the
+/// `MISSION_WRITES`, plus `NEW_DONOR_WRITES` for a first gift, the donor's
+/// asset breakdown if they have one, `ROUND_WRITES` per open round and a
+/// pending callback if the mission has yet to reach its target.
+fn entry_writes(env: &Env, mission_id: &BytesN<32>, mission: &Mission, donor: &Address) -> u32 {
+    let mut writes = MISSION_WRITES;
+    if contribution::load(env, mission_id, donor).is_none() {
+        writes += NEW_DONOR_WRITES;
+    }
+    if storage::has(env, &DataKey::ContributionAssets(mission_id.clone(), donor.clone())) {
+        writes += 1;
+    }
+    writes += ROUND_WRITES * matching::open_round_count(env, mission_id);
+    if !mission.target_reached() && callback::is_registered(env, mission_id) {
+        writes += 1;
+    }
+    writes
+}
+
+#[contractimpl]
+impl MissionFundContract {
+    /// Donates to several missions at once, each `amount` in that mission's
+    /// reference asset, under a single signature. The donor pays one
+    /// transfer per asset (and one fee transfer) for the whole batch and
+    /// gets a contribution NFT per mission, whose token ids are returned in
+    /// order. Each mission may appear once; if any entry cannot be funded
+    /// the whole batch fails. A batch whose ledger writes could exceed the
+    /// network limit fails with `BatchTooLarge` before anything moves, and
+    /// must be split.
+    pub fn fund_missions(
+        env: Env,
+        donor: Address,
+        entries: Vec<(BytesN<32>, i128)>,
+    ) -> Result<Vec<u64>, Error> {
+        upgrade::ensure_current(&env)?;
+        donor.require_auth();
+        if entries.is_empty() {
+            return Err(Error::InvalidConfig);
+        }
+        if entries.len() > MAX_BATCH_FUNDING {
+            return Err(Error::BatchTooLarge);
+        }
+
+        let mut assets = Vec::new(&env);
+        let mut nets: Map<Address, i128> = Map::new(&env);
+        let mut fees: Map<Address, i128> = Map::new(&env);
+        let mut collector = None;
+        let mut writes = SHARED_WRITES;
+        for (i, (mission_id, amount)) in entries.iter().enumerate() {
+            if amount <= 0 {
+                return Err(Error::InvalidAmount);
+            }
+            if entries.iter().take(i).any(|(earlier, _)| earlier == mission_id) {
+                return Err(Error::InvalidConfig);
+            }
+            let mission = load_mission(&env, &mission_id)?;
+            writes += entry_writes(&env, &mission_id, &mission, &donor);
+            let asset = mission.reference_asset;
+            if !nets.contains_key(asset.clone()) {
+                writes += ASSET_WRITES;
+            }
+            let (quote, fee_collector) = fee::quote(&env, amount)?;
+            nets.set(asset.clone(), math::add(nets.get(asset.clone()).unwrap_or(0), quote.net)?);
+            fees.set(asset.clone(), math::add(fees.get(asset.clone()).unwrap_or(0), quote.fee)?);
+            collector = fee_collector;
+            assets.push_back(asset);
+        }
+        if writes > TX_WRITE_LIMIT {
+            return Err(Error::BatchTooLarge);
+        }
+
+        for (asset, net) in nets.iter() {
+            let token = token::Client::new(&env, &asset);
+            token.transfer(&donor, &env.current_contract_address(), &net);
+            let fee = fees.get_unchecked(asset);
+            if let Some(collector) = collector.as_ref().filter(|_| fee > 0) {
+                token.transfer(&donor, collector, &fee);
+            }
+        }
+
+        let mut token_ids = Vec::new(&env);
+        for ((mission_id, amount), asset) in entries.iter().zip(assets.iter()) {
+            token_ids.push_back(accept_donation(
+                &env,
+                &mission_id,
+                &donor,
+                &asset,
+                amount,
+                Payment::Prepaid,
+            )?);
+        }
+        Ok(token_ids)
+    }
+}
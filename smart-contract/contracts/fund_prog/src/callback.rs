@@ -0,0 +1,127 @@
+use soroban_sdk::{contractimpl, contracttype, vec, Address, BytesN, Env, IntoVal, Symbol, Val};
+
+use crate::storage::{self, DataKey};
+use crate::{events, load_mission, team, upgrade, Error, Mission, MissionFundContract, MissionFundContractArgs, MissionFundContractClient};
+
+/// A partner contract function to call once a mission reaches its target,
+/// as `function(mission_id: BytesN<32>, target_amount: i128, funded_amount: i128)`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TargetCallback {
+    pub contract: Address,
+    pub function: Symbol,
+}
+
+/// Calls `callback` as `function(mission_id, target_amount, funded_amount)`
+/// and reports the outcome in the `callback` event. The host refuses any
+/// attempt by the callee to call back into this contract. Returns whether
+/// the call succeeded.
+fn invoke(
+    env: &Env,
+    mission_id: &BytesN<32>,
+    target_amount: i128,
+    funded_amount: i128,
+    callback: &TargetCallback,
+) -> bool {
+    let args = vec![
+        env,
+        mission_id.into_val(env),
+        target_amount.into_val(env),
+        funded_amount.into_val(env),
+    ];
+    let succeeded = env
+        .try_invoke_contract::<Val, soroban_sdk::Error>(&callback.contract, &callback.function, args)
+        .is_ok();
+    events::callback_invoked(env, mission_id, &callback.contract, succeeded);
+    succeeded
+}
+
+/// Notifies the partner of a mission whose target the donation just
+/// reached. With `inline` the partner is called straight away; a call that
+/// fails, or is not made, is left pending for `notify_target` with the
+/// funded amount the partner is told. A failed call does not undo the
+/// donation, but a callee that runs the whole transaction out of budget
+/// does, as `try_invoke_contract` cannot catch that.
+pub(crate) fn target_reached(env: &Env, mission_id: &BytesN<32>, mission: &Mission, inline: bool) {
+    let key = DataKey::TargetCallback(mission_id.clone());
+    let Some(callback) = storage::read::<TargetCallback>(env, &key) else {
+        return;
+    };
+    let funded_amount = mission.funded_amount;
+    if inline && invoke(env, mission_id, mission.target_amount, funded_amount, &callback) {
+        return;
+    }
+    storage::write(env, &DataKey::PendingCallback(mission_id.clone()), &funded_amount);
+    events::callback_due(env, mission_id, &callback.contract, funded_amount);
+}
+
+/// Whether the mission has a callback registered.
+pub(crate) fn is_registered(env: &Env, mission_id: &BytesN<32>) -> bool {
+    storage::has(env, &DataKey::TargetCallback(mission_id.clone()))
+}
+
+/// Registers or removes the mission's callback; see `set_target_callback`.
+/// Removing it also drops a notification still pending.
+pub(crate) fn set(env: &Env, mission_id: &BytesN<32>, callback: Option<TargetCallback>) {
+    let key = DataKey::TargetCallback(mission_id.clone());
+    match &callback {
+        Some(callback) => storage::write(env, &key, callback),
+        None => {
+            env.storage().persistent().remove(&key);
+            env.storage()
+                .persistent()
+                .remove(&DataKey::PendingCallback(mission_id.clone()));
+        }
+    }
+    events::target_callback_set(env, mission_id, &callback);
+}
+
+#[contractimpl]
+impl MissionFundContract {
+    /// Owner registers the contract function to notify once the mission
+    /// reaches its target, or removes it with `None`. The partner is called
+    /// by the donation that reaches the target, except in a `fund_missions`
+    /// batch or if that call fails; the call is then left pending for
+    /// `notify_target`. Team-owned missions
+    /// set it through a `TeamAction::SetTargetCallback` proposal.
+    pub fn set_target_callback(
+        env: Env,
+        mission_id: BytesN<32>,
+        callback: Option<TargetCallback>,
+    ) -> Result<(), Error> {
+        upgrade::ensure_current(&env)?;
+        let mission = load_mission(&env, &mission_id)?;
+        mission.owner.require_auth();
+        team::ensure_solo(&env, &mission_id)?;
+        set(&env, &mission_id, callback);
+        Ok(())
+    }
+
+    pub fn get_target_callback(env: Env, mission_id: BytesN<32>) -> Option<TargetCallback> {
+        storage::read(&env, &DataKey::TargetCallback(mission_id))
+    }
+
+    /// Retries the call to the partner of a mission that reached its
+    /// target, left pending when the donation could not make it. Anyone may
+    /// trigger it. A failed call is reported in the `callback` event and
+    /// stays pending, so it can be retried; returns whether it succeeded.
+    pub fn notify_target(env: Env, mission_id: BytesN<32>) -> Result<bool, Error> {
+        upgrade::ensure_current(&env)?;
+        let pending = DataKey::PendingCallback(mission_id.clone());
+        let funded_amount: i128 =
+            storage::read(&env, &pending).ok_or(Error::NoPendingCallback)?;
+        let mission = load_mission(&env, &mission_id)?;
+        let callback: TargetCallback =
+            storage::read(&env, &DataKey::TargetCallback(mission_id.clone()))
+                .ok_or(Error::NoPendingCallback)?;
+        let succeeded = invoke(&env, &mission_id, mission.target_amount, funded_amount, &callback);
+        if succeeded {
+            env.storage().persistent().remove(&pending);
+        }
+        Ok(succeeded)
+    }
+
+    pub fn pending_callback(env: Env, mission_id: BytesN<32>) -> bool {
+        storage::has(&env, &DataKey::PendingCallback(mission_id))
+    }
+}
@@ -0,0 +1,233 @@
+use soroban_sdk::{contractimpl, contracttype, Address, BytesN, Env, Map, Vec};
+
+use crate::index::MAX_PAGE_SIZE;
+use crate::storage::{self, DataKey};
+use crate::{load_mission, upgrade, Error, MissionFundContract, MissionFundContractArgs, MissionFundContractClient};
+
+/// Most donors one `bump_donors` call extends. Each donor has up to five
+/// records to read, and a transaction may read no more than 40 entries.
+pub const MAX_BUMP_DONORS: u32 = 7;
+
+/// What one donor has put into one mission, valued in the mission's
+/// reference asset. `amount` is what is currently held for them; `refunded`
+/// is what has already been paid back. The per-asset breakdown is kept
+/// alongside under `DataKey::ContributionAssets`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Contribution {
+    pub amount: i128,
+    pub refunded: i128,
+    pub count: u32,
+    pub first_at: u64,
+    pub last_at: u64,
+}
+
+pub(crate) fn load(env: &Env, mission_id: &BytesN<32>, donor: &Address) -> Option<Contribution> {
+    storage::read(
+        env,
+        &DataKey::Contribution(mission_id.clone(), donor.clone()),
+    )
+}
+
+pub(crate) fn save(
+    env: &Env,
+    mission_id: &BytesN<32>,
+    donor: &Address,
+    contribution: &Contribution,
+) {
+    storage::write(
+        env,
+        &DataKey::Contribution(mission_id.clone(), donor.clone()),
+        contribution,
+    );
+}
+
+/// `donor`'s outstanding contribution per asset. Records written before
+/// missions accepted several assets have no breakdown: all of it is in the
+/// reference asset.
+pub(crate) fn assets(
+    env: &Env,
+    mission_id: &BytesN<32>,
+    donor: &Address,
+    reference: &Address,
+) -> Map<Address, i128> {
+    let key = DataKey::ContributionAssets(mission_id.clone(), donor.clone());
+    storage::read(env, &key).unwrap_or_else(|| {
+        let mut assets = Map::new(env);
+        if let Some(contribution) = load(env, mission_id, donor) {
+            assets.set(reference.clone(), contribution.amount);
+        }
+        assets
+    })
+}
+
+pub(crate) fn save_assets(
+    env: &Env,
+    mission_id: &BytesN<32>,
+    donor: &Address,
+    assets: &Map<Address, i128>,
+) {
+    storage::write(
+        env,
+        &DataKey::ContributionAssets(mission_id.clone(), donor.clone()),
+        assets,
+    );
+}
+
+pub(crate) fn donor_count(env: &Env, mission_id: &BytesN<32>) -> u32 {
+    storage::read(env, &DataKey::DonorCount(mission_id.clone())).unwrap_or(0)
+}
+
+fn donor_mission_count(env: &Env, donor: &Address) -> u32 {
+    storage::read(env, &DataKey::DonorMissionCount(donor.clone())).unwrap_or(0)
+}
+
+/// Adds a donation of `net` in `asset`, worth `amount` in the mission's
+/// `reference` asset, to `donor`'s record for the mission, registering them
+/// in the mission's donor list and their own mission list on first gift.
+pub(crate) fn record(
+    env: &Env,
+    mission_id: &BytesN<32>,
+    donor: &Address,
+    reference: &Address,
+    asset: &Address,
+    net: i128,
+    amount: i128,
+) -> Contribution {
+    // Donors who only ever gave in the reference asset need no breakdown;
+    // `assets` derives it from their contribution.
+    let key = DataKey::ContributionAssets(mission_id.clone(), donor.clone());
+    if asset != reference || storage::has(env, &key) {
+        let mut assets = assets(env, mission_id, donor, reference);
+        assets.set(asset.clone(), assets.get(asset.clone()).unwrap_or(0) + net);
+        save_assets(env, mission_id, donor, &assets);
+    }
+
+    let now = env.ledger().timestamp();
+    let contribution = match load(env, mission_id, donor) {
+        Some(mut existing) => {
+            existing.amount += amount;
+            existing.count += 1;
+            existing.last_at = now;
+            existing
+        }
+        None => {
+            let position = donor_count(env, mission_id);
+            storage::write(env, &DataKey::DonorAt(mission_id.clone(), position), donor);
+            storage::write(
+                env,
+                &DataKey::DonorCount(mission_id.clone()),
+                &(position + 1),
+            );
+
+            let listed = donor_mission_count(env, donor);
+            storage::write(env, &DataKey::DonorMissionAt(donor.clone(), listed), mission_id);
+            storage::write(env, &DataKey::DonorMissionCount(donor.clone()), &(listed + 1));
+
+            Contribution {
+                amount,
+                refunded: 0,
+                count: 1,
+                first_at: now,
+                last_at: now,
+            }
+        }
+    };
+    save(env, mission_id, donor, &contribution);
+    contribution
+}
+
+#[contractimpl]
+impl MissionFundContract {
+    pub fn get_contribution(
+        env: Env,
+        mission_id: BytesN<32>,
+        donor: Address,
+    ) -> Result<Contribution, Error> {
+        load(&env, &mission_id, &donor).ok_or(Error::NotContributor)
+    }
+
+    /// `donor`'s outstanding contribution to `mission_id` in each asset.
+    pub fn get_contribution_assets(
+        env: Env,
+        mission_id: BytesN<32>,
+        donor: Address,
+    ) -> Result<Map<Address, i128>, Error> {
+        upgrade::ensure_current(&env)?;
+        let mission = load_mission(&env, &mission_id)?;
+        load(&env, &mission_id, &donor).ok_or(Error::NotContributor)?;
+        Ok(assets(&env, &mission_id, &donor, &mission.reference_asset))
+    }
+
+    pub fn donor_count(env: Env, mission_id: BytesN<32>) -> u32 {
+        donor_count(&env, &mission_id)
+    }
+
+    /// Donors to `mission_id` in order of their first gift, with their
+    /// contribution records.
+    pub fn list_donors(
+        env: Env,
+        mission_id: BytesN<32>,
+        start: u32,
+        limit: u32,
+    ) -> Vec<(Address, Contribution)> {
+        let end =
+            donor_count(&env, &mission_id).min(start.saturating_add(limit.min(MAX_PAGE_SIZE)));
+        let mut donors = Vec::new(&env);
+        for position in start..end {
+            let donor: Option<Address> =
+                storage::read(&env, &DataKey::DonorAt(mission_id.clone(), position));
+            if let Some(donor) = donor {
+                if let Some(contribution) = load(&env, &mission_id, &donor) {
+                    donors.push_back((donor, contribution));
+                }
+            }
+        }
+        donors
+    }
+
+    /// Extends the lifetime of the records kept for a page of the mission's
+    /// donors: their place in the donor list, contribution, per-asset
+    /// breakdown, tier and pledge. Anyone may call it, alongside
+    /// `bump_mission`; returns how many donors were covered.
+    pub fn bump_donors(
+        env: Env,
+        mission_id: BytesN<32>,
+        start: u32,
+        limit: u32,
+    ) -> Result<u32, Error> {
+        if !storage::has(&env, &DataKey::Mission(mission_id.clone())) {
+            return Err(Error::MissionNotFound);
+        }
+        let end =
+            donor_count(&env, &mission_id).min(start.saturating_add(limit.min(MAX_BUMP_DONORS)));
+        for position in start..end {
+            let donor: Option<Address> =
+                storage::read(&env, &DataKey::DonorAt(mission_id.clone(), position));
+            let Some(donor) = donor else {
+                continue;
+            };
+            for key in [
+                DataKey::Contribution(mission_id.clone(), donor.clone()),
+                DataKey::ContributionAssets(mission_id.clone(), donor.clone()),
+                DataKey::Pledge(mission_id.clone(), donor),
+            ] {
+                storage::extend_if_present(&env, &key);
+            }
+        }
+        Ok(end.saturating_sub(start))
+    }
+
+    /// Missions `donor` has given to, in order of their first gift.
+    pub fn donor_missions(env: Env, donor: Address, start: u32, limit: u32) -> Vec<BytesN<32>> {
+        let end =
+            donor_mission_count(&env, &donor).min(start.saturating_add(limit.min(MAX_PAGE_SIZE)));
+        let mut missions = Vec::new(&env);
+        for position in start..end {
+            if let Some(id) = storage::read(&env, &DataKey::DonorMissionAt(donor.clone(), position)) {
+                missions.push_back(id);
+            }
+        }
+        missions
+    }
+}
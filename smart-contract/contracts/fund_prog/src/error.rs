@@ -0,0 +1,55 @@
+use soroban_sdk::contracterror;
+
+/// Machine-readable failure codes returned by every fallible entry point.
+/// Values are part of the contract interface; append new variants, never
+/// renumber existing ones.
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum Error {
+    AlreadyInitialized = 1,
+    NotInitialized = 2,
+    MissionNotFound = 3,
+    AlreadyExists = 4,
+    InvalidAmount = 5,
+    NftNotFound = 6,
+    NotOwner = 7,
+    NotApproved = 8,
+    NotFunded = 9,
+    InvalidMilestones = 10,
+    MilestoneNotFound = 11,
+    InvalidMilestoneStatus = 12,
+    InvalidDeadline = 13,
+    DeadlinePassed = 14,
+    RefundUnavailable = 15,
+    NothingToClaim = 16,
+    FundsInEscrow = 17,
+    InvalidConfig = 18,
+    NotContributor = 19,
+    Paused = 20,
+    MissionPaused = 21,
+    NoPendingAdmin = 22,
+    FeeTooHigh = 23,
+    MigrationPending = 24,
+    MissionClosed = 25,
+    MissionCancelled = 26,
+    TooManyChanges = 27,
+    UnsupportedAsset = 28,
+    AssetsLocked = 29,
+    Overflow = 30,
+    RoundNotFound = 31,
+    RoundOpen = 32,
+    RoundEnded = 33,
+    RoundFull = 34,
+    PledgeNotFound = 35,
+    PledgeNotDue = 36,
+    TooManyPledges = 37,
+    TeamApprovalRequired = 38,
+    NotTeamMember = 39,
+    ProposalNotFound = 40,
+    ProposalClosed = 41,
+    DonorNotVerified = 42,
+    BatchTooLarge = 43,
+    NoPendingCallback = 44,
+    MilestonesCompleted = 45,
+}
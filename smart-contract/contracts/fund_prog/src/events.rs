@@ -0,0 +1,420 @@
+//! Events published by the contract. Topic symbols are part of the public
+//! interface consumed by indexers: add new ones, never rename existing ones.
+//!
+//! Every event has two symbol topics (category, action) followed by the
+//! subject id (mission id or token id) where there is one; the payload is a
+//! tuple.
+
+use soroban_sdk::{symbol_short, Address, BytesN, Env, String, Symbol, Vec};
+
+use crate::admin::Role;
+use crate::callback::TargetCallback;
+use crate::pledge::Pledge;
+use crate::rarity::{Rarity, RarityThresholds};
+use crate::team::Team;
+
+const ADMIN: Symbol = symbol_short!("admin");
+const MISSION: Symbol = symbol_short!("mission");
+const MILESTONE: Symbol = symbol_short!("milestone");
+const NFT: Symbol = symbol_short!("nft");
+const ROUND: Symbol = symbol_short!("round");
+
+/// `(mission, register, mission_id)` → `(owner, target_amount, deadline)`
+pub(crate) fn mission_registered(
+    env: &Env,
+    mission_id: &BytesN<32>,
+    owner: &Address,
+    target_amount: i128,
+    deadline: u64,
+) {
+    env.events().publish(
+        (MISSION, symbol_short!("register"), mission_id.clone()),
+        (owner.clone(), target_amount, deadline),
+    );
+}
+
+/// `(mission, migrate, mission_id)` → `legacy_funded`
+pub(crate) fn mission_migrated(env: &Env, mission_id: &BytesN<32>, legacy_funded: i128) {
+    env.events().publish(
+        (MISSION, symbol_short!("migrate"), mission_id.clone()),
+        legacy_funded,
+    );
+}
+
+/// `(mission, update, mission_id)` → `(title, desc, image_url)`
+pub(crate) fn mission_updated(
+    env: &Env,
+    mission_id: &BytesN<32>,
+    title: &String,
+    desc: &String,
+    image_url: &String,
+) {
+    env.events().publish(
+        (MISSION, symbol_short!("update"), mission_id.clone()),
+        (title.clone(), desc.clone(), image_url.clone()),
+    );
+}
+
+/// `(mission, close, mission_id)` → `()`
+pub(crate) fn mission_closed(env: &Env, mission_id: &BytesN<32>) {
+    env.events()
+        .publish((MISSION, symbol_short!("close"), mission_id.clone()), ());
+}
+
+/// `(mission, cancel, mission_id)` → `refund_pool`
+pub(crate) fn mission_cancelled(env: &Env, mission_id: &BytesN<32>, refund_pool: i128) {
+    env.events().publish(
+        (MISSION, symbol_short!("cancel"), mission_id.clone()),
+        refund_pool,
+    );
+}
+
+/// `(mission, funded, mission_id)` → `(donor, amount, funded_amount)`
+pub(crate) fn funded(
+    env: &Env,
+    mission_id: &BytesN<32>,
+    donor: &Address,
+    amount: i128,
+    total: i128,
+) {
+    env.events().publish(
+        (MISSION, symbol_short!("funded"), mission_id.clone()),
+        (donor.clone(), amount, total),
+    );
+}
+
+/// `(mission, target, mission_id)` → `(target_amount, funded_amount)`
+pub(crate) fn target_reached(env: &Env, mission_id: &BytesN<32>, target: i128, total: i128) {
+    env.events().publish(
+        (MISSION, symbol_short!("target"), mission_id.clone()),
+        (target, total),
+    );
+}
+
+/// `(mission, refund, mission_id)` → `(donor, amount)`
+pub(crate) fn refunded(env: &Env, mission_id: &BytesN<32>, donor: &Address, amount: i128) {
+    env.events().publish(
+        (MISSION, symbol_short!("refund"), mission_id.clone()),
+        (donor.clone(), amount),
+    );
+}
+
+/// `(mission, withdraw, mission_id)` → `(to, amount)`
+pub(crate) fn withdrawn(env: &Env, mission_id: &BytesN<32>, to: &Address, amount: i128) {
+    env.events().publish(
+        (MISSION, symbol_short!("withdraw"), mission_id.clone()),
+        (to.clone(), amount),
+    );
+}
+
+/// `(milestone, submit, mission_id)` → `(index, evidence_hash)`
+pub(crate) fn milestone_submitted(
+    env: &Env,
+    mission_id: &BytesN<32>,
+    index: u32,
+    evidence: &BytesN<32>,
+) {
+    env.events().publish(
+        (MILESTONE, symbol_short!("submit"), mission_id.clone()),
+        (index, evidence.clone()),
+    );
+}
+
+/// `(milestone, review, mission_id)` → `(index, approved, released)`
+pub(crate) fn milestone_reviewed(
+    env: &Env,
+    mission_id: &BytesN<32>,
+    index: u32,
+    approved: bool,
+    released: i128,
+) {
+    env.events().publish(
+        (MILESTONE, symbol_short!("review"), mission_id.clone()),
+        (index, approved, released),
+    );
+}
+
+/// `(nft, mint, token_id)` → `(owner, mission_id)`
+pub(crate) fn nft_minted(env: &Env, token_id: u64, owner: &Address, mission_id: &BytesN<32>) {
+    env.events().publish(
+        (NFT, symbol_short!("mint"), token_id),
+        (owner.clone(), mission_id.clone()),
+    );
+}
+
+/// `(nft, retier, mission_id)` → `(donor, rarity)`
+pub(crate) fn donor_retiered(env: &Env, mission_id: &BytesN<32>, donor: &Address, rarity: Rarity) {
+    env.events().publish(
+        (NFT, symbol_short!("retier"), mission_id.clone()),
+        (donor.clone(), rarity),
+    );
+}
+
+/// `(nft, transfer, token_id)` → `(from, to)`
+pub(crate) fn nft_transferred(env: &Env, token_id: u64, from: &Address, to: &Address) {
+    env.events().publish(
+        (NFT, symbol_short!("transfer"), token_id),
+        (from.clone(), to.clone()),
+    );
+}
+
+/// `(nft, approve, token_id)` → `(owner, operator)`
+pub(crate) fn nft_approved(env: &Env, token_id: u64, owner: &Address, operator: &Option<Address>) {
+    env.events().publish(
+        (NFT, symbol_short!("approve"), token_id),
+        (owner.clone(), operator.clone()),
+    );
+}
+
+/// `(admin, propose)` → `(admin, new_admin)`
+pub(crate) fn admin_proposed(env: &Env, admin: &Address, new_admin: &Address) {
+    env.events().publish(
+        (ADMIN, symbol_short!("propose")),
+        (admin.clone(), new_admin.clone()),
+    );
+}
+
+/// `(admin, accept)` → `new_admin`
+pub(crate) fn admin_accepted(env: &Env, new_admin: &Address) {
+    env.events()
+        .publish((ADMIN, symbol_short!("accept")), new_admin.clone());
+}
+
+/// `(admin, role)` → `(role, holder)`
+pub(crate) fn role_set(env: &Env, role: Role, holder: &Option<Address>) {
+    env.events()
+        .publish((ADMIN, symbol_short!("role")), (role, holder.clone()));
+}
+
+/// `(admin, pause)` → `paused`
+pub(crate) fn paused(env: &Env, paused: bool) {
+    env.events().publish((ADMIN, symbol_short!("pause")), paused);
+}
+
+/// `(mission, pause, mission_id)` → `paused`
+pub(crate) fn mission_paused(env: &Env, mission_id: &BytesN<32>, paused: bool) {
+    env.events().publish(
+        (MISSION, symbol_short!("pause"), mission_id.clone()),
+        paused,
+    );
+}
+
+/// `(admin, fee)` → `fee_bps`
+pub(crate) fn fee_set(env: &Env, fee_bps: u32) {
+    env.events().publish((ADMIN, symbol_short!("fee")), fee_bps);
+}
+
+/// `(admin, rarity)` → `thresholds`
+pub(crate) fn rarity_thresholds_set(env: &Env, thresholds: &RarityThresholds) {
+    env.events()
+        .publish((ADMIN, symbol_short!("rarity")), *thresholds);
+}
+
+/// `(admin, base_uri)` → `base_uri`
+pub(crate) fn base_uri_set(env: &Env, base_uri: &String) {
+    env.events()
+        .publish((ADMIN, symbol_short!("base_uri")), base_uri.clone());
+}
+
+/// `(admin, rate, asset)` → `rate`
+pub(crate) fn rate_set(env: &Env, asset: &Address, rate: Option<i128>) {
+    env.events()
+        .publish((ADMIN, symbol_short!("rate"), asset.clone()), rate);
+}
+
+/// `(mission, assets, mission_id)` → `(reference, accepted)`
+pub(crate) fn mission_assets_set(
+    env: &Env,
+    mission_id: &BytesN<32>,
+    reference: &Address,
+    accepted: &Vec<Address>,
+) {
+    env.events().publish(
+        (MISSION, symbol_short!("assets"), mission_id.clone()),
+        (reference.clone(), accepted.clone()),
+    );
+}
+
+/// `(mission, fund_asst, mission_id)` → `(donor, asset, amount)`, alongside
+/// `funded` for donations in anything but the reference asset. `amount` is
+/// in the donated asset; `funded` carries its reference value.
+pub(crate) fn funded_in_asset(
+    env: &Env,
+    mission_id: &BytesN<32>,
+    donor: &Address,
+    asset: &Address,
+    amount: i128,
+) {
+    env.events().publish(
+        (MISSION, symbol_short!("fund_asst"), mission_id.clone()),
+        (donor.clone(), asset.clone(), amount),
+    );
+}
+
+/// `(mission, pledge, mission_id)` → `(donor, asset, amount, period, cap)`
+pub(crate) fn pledged(env: &Env, mission_id: &BytesN<32>, donor: &Address, pledge: &Pledge) {
+    env.events().publish(
+        (MISSION, symbol_short!("pledge"), mission_id.clone()),
+        (
+            donor.clone(),
+            pledge.asset.clone(),
+            pledge.amount,
+            pledge.period,
+            pledge.cap,
+        ),
+    );
+}
+
+/// `(mission, collect, mission_id)` → `(donor, installment, collected)`,
+/// alongside the usual `funded` event for the installment.
+pub(crate) fn pledge_collected(
+    env: &Env,
+    mission_id: &BytesN<32>,
+    donor: &Address,
+    installment: i128,
+    collected: i128,
+) {
+    env.events().publish(
+        (MISSION, symbol_short!("collect"), mission_id.clone()),
+        (donor.clone(), installment, collected),
+    );
+}
+
+/// `(mission, unpledge, mission_id)` → `donor`
+pub(crate) fn pledge_cancelled(env: &Env, mission_id: &BytesN<32>, donor: &Address) {
+    env.events()
+        .publish((MISSION, symbol_short!("unpledge"), mission_id.clone()), donor.clone());
+}
+
+/// `(mission, team, mission_id)` → `(signers, threshold, payout)`
+pub(crate) fn team_set(env: &Env, mission_id: &BytesN<32>, team: &Team) {
+    env.events().publish(
+        (MISSION, symbol_short!("team"), mission_id.clone()),
+        (team.signers.clone(), team.threshold, team.payout.clone()),
+    );
+}
+
+/// `(mission, propose, mission_id)` → `(proposal_id, signer)`
+pub(crate) fn proposed(env: &Env, mission_id: &BytesN<32>, proposal_id: u32, signer: &Address) {
+    env.events().publish(
+        (MISSION, symbol_short!("propose"), mission_id.clone()),
+        (proposal_id, signer.clone()),
+    );
+}
+
+/// `(mission, approve, mission_id)` → `(proposal_id, signer)`
+pub(crate) fn proposal_approved(
+    env: &Env,
+    mission_id: &BytesN<32>,
+    proposal_id: u32,
+    signer: &Address,
+) {
+    env.events().publish(
+        (MISSION, symbol_short!("approve"), mission_id.clone()),
+        (proposal_id, signer.clone()),
+    );
+}
+
+/// `(mission, execute, mission_id)` → `proposal_id`
+pub(crate) fn proposal_executed(env: &Env, mission_id: &BytesN<32>, proposal_id: u32) {
+    env.events()
+        .publish((MISSION, symbol_short!("execute"), mission_id.clone()), proposal_id);
+}
+
+/// `(mission, cb_set, mission_id)` → `callback`
+pub(crate) fn target_callback_set(
+    env: &Env,
+    mission_id: &BytesN<32>,
+    callback: &Option<TargetCallback>,
+) {
+    env.events().publish(
+        (MISSION, symbol_short!("cb_set"), mission_id.clone()),
+        callback.clone(),
+    );
+}
+
+/// `(mission, cb_due, mission_id)` → `(contract, funded_amount)`
+pub(crate) fn callback_due(
+    env: &Env,
+    mission_id: &BytesN<32>,
+    contract: &Address,
+    funded_amount: i128,
+) {
+    env.events().publish(
+        (MISSION, symbol_short!("cb_due"), mission_id.clone()),
+        (contract.clone(), funded_amount),
+    );
+}
+
+/// `(mission, callback, mission_id)` → `(contract, succeeded)`
+pub(crate) fn callback_invoked(
+    env: &Env,
+    mission_id: &BytesN<32>,
+    contract: &Address,
+    succeeded: bool,
+) {
+    env.events().publish(
+        (MISSION, symbol_short!("callback"), mission_id.clone()),
+        (contract.clone(), succeeded),
+    );
+}
+
+/// `(mission, fee, mission_id)` → `(donor, fee)`
+pub(crate) fn fee_charged(env: &Env, mission_id: &BytesN<32>, donor: &Address, fee: i128) {
+    env.events().publish(
+        (MISSION, symbol_short!("fee"), mission_id.clone()),
+        (donor.clone(), fee),
+    );
+}
+
+/// `(admin, upgrade)` → `new_wasm_hash`
+pub(crate) fn upgraded(env: &Env, new_wasm_hash: &BytesN<32>) {
+    env.events()
+        .publish((ADMIN, symbol_short!("upgrade")), new_wasm_hash.clone());
+}
+
+/// `(admin, schema)` → `version`
+pub(crate) fn schema_migrated(env: &Env, version: u32) {
+    env.events().publish((ADMIN, symbol_short!("schema")), version);
+}
+
+/// `(round, create, round_id)` → `(sponsor, pool, start, end)`
+pub(crate) fn round_created(
+    env: &Env,
+    round_id: u32,
+    sponsor: &Address,
+    pool: i128,
+    start: u64,
+    end: u64,
+) {
+    env.events().publish(
+        (ROUND, symbol_short!("create"), round_id),
+        (sponsor.clone(), pool, start, end),
+    );
+}
+
+/// `(round, join, round_id)` → `mission_id`
+pub(crate) fn round_joined(env: &Env, round_id: u32, mission_id: &BytesN<32>) {
+    env.events()
+        .publish((ROUND, symbol_short!("join"), round_id), mission_id.clone());
+}
+
+/// `(round, finalize, round_id)` → `(matched, returned)`
+pub(crate) fn round_finalized(env: &Env, round_id: u32, matched: i128, returned: i128) {
+    env.events()
+        .publish((ROUND, symbol_short!("finalize"), round_id), (matched, returned));
+}
+
+/// `(round, match, round_id)` → `(mission_id, to, amount)`
+pub(crate) fn match_claimed(
+    env: &Env,
+    round_id: u32,
+    mission_id: &BytesN<32>,
+    to: &Address,
+    amount: i128,
+) {
+    env.events().publish(
+        (ROUND, symbol_short!("match"), round_id),
+        (mission_id.clone(), to.clone(), amount),
+    );
+}
@@ -0,0 +1,65 @@
+use soroban_sdk::{contractimpl, contracttype, Address, Env};
+
+use crate::admin::{self, Role};
+use crate::storage::DataKey;
+use crate::{events, math, Error, MissionFundContract, MissionFundContractArgs, MissionFundContractClient};
+
+/// Hard ceiling on the platform fee (10%), whatever the admin asks for.
+pub const MAX_FEE_BPS: u32 = 1_000;
+
+/// How a donation of `amount` is split: `fee` goes to the fee collector and
+/// `net` is what the mission receives.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FeeQuote {
+    pub amount: i128,
+    pub fee: i128,
+    pub net: i128,
+}
+
+fn fee_bps(env: &Env) -> u32 {
+    env.storage().instance().get(&DataKey::FeeBps).unwrap_or(0)
+}
+
+/// Splits `amount` under the current fee. Returns the collector alongside
+/// the quote; no fee is charged while nobody holds the `FeeCollector` role.
+pub(crate) fn quote(env: &Env, amount: i128) -> Result<(FeeQuote, Option<Address>), Error> {
+    let collector = admin::role(env, Role::FeeCollector);
+    let fee = match collector {
+        Some(_) => math::mul_div(amount, fee_bps(env) as i128, 10_000)?,
+        None => 0,
+    };
+    let quote = FeeQuote {
+        amount,
+        fee,
+        net: amount - fee,
+    };
+    Ok((quote, collector))
+}
+
+#[contractimpl]
+impl MissionFundContract {
+    pub fn get_fee_bps(env: Env) -> u32 {
+        fee_bps(&env)
+    }
+
+    /// Sets the platform fee in basis points, capped at `MAX_FEE_BPS`.
+    pub fn set_fee_bps(env: Env, fee_bps: u32) -> Result<(), Error> {
+        admin::require_admin(&env)?;
+        if fee_bps > MAX_FEE_BPS {
+            return Err(Error::FeeTooHigh);
+        }
+        env.storage().instance().set(&DataKey::FeeBps, &fee_bps);
+        events::fee_set(&env, fee_bps);
+        Ok(())
+    }
+
+    /// What a donation of `amount` would cost in fees right now, so the UI
+    /// can show the net amount the mission receives.
+    pub fn get_fee_quote(env: Env, amount: i128) -> Result<FeeQuote, Error> {
+        if amount <= 0 {
+            return Err(Error::InvalidAmount);
+        }
+        Ok(quote(&env, amount)?.0)
+    }
+}
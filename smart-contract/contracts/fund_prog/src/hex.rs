@@ -0,0 +1,14 @@
+//! Lowercase hex encoding into caller-provided buffers, since `no_std` has
+//! no `format!`.
+
+const DIGITS: &[u8; 16] = b"0123456789abcdef";
+
+/// Writes the hex form of `bytes` to the start of `out`, which must hold at
+/// least `2 * bytes.len()` bytes, and returns the number of bytes written.
+pub(crate) fn encode(bytes: &[u8], out: &mut [u8]) -> usize {
+    for (i, byte) in bytes.iter().enumerate() {
+        out[2 * i] = DIGITS[(byte >> 4) as usize];
+        out[2 * i + 1] = DIGITS[(byte & 0x0f) as usize];
+    }
+    2 * bytes.len()
+}
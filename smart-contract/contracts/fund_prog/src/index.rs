@@ -0,0 +1,119 @@
+use soroban_sdk::{contractimpl, contracttype, Address, BytesN, Env, Vec};
+
+use crate::storage::{self, DataKey};
+use crate::{load_mission, Mission, MissionFundContract, MissionFundContractArgs, MissionFundContractClient, MissionStatus};
+
+/// Largest page any listing query returns, whatever `limit` is passed.
+/// Listing a mission or donor reads two ledger entries, and a transaction
+/// may read no more than 40.
+pub const MAX_PAGE_SIZE: u32 = 15;
+
+/// Upper bound on index positions a single `missions_by_status` call
+/// inspects, so a sparse status cannot blow the read budget.
+pub const MAX_SCAN: u32 = 15;
+
+/// A page of `missions_by_status` results. `next` is the index position to
+/// pass as `start` to continue the scan, or `None` once the end is reached.
+#[contracttype]
+#[derive(Clone)]
+pub struct MissionPage {
+    pub missions: Vec<(BytesN<32>, Mission)>,
+    pub next: Option<u32>,
+}
+
+fn count(env: &Env) -> u32 {
+    env.storage().instance().get(&DataKey::MissionCount).unwrap_or(0)
+}
+
+fn owner_count(env: &Env, owner: &Address) -> u32 {
+    storage::read(env, &DataKey::OwnerMissionCount(owner.clone())).unwrap_or(0)
+}
+
+/// Appends a newly stored mission to the global and per-owner indexes.
+pub(crate) fn add(env: &Env, mission_id: &BytesN<32>, owner: &Address) {
+    let position = count(env);
+    storage::write(env, &DataKey::MissionAt(position), mission_id);
+    env.storage()
+        .instance()
+        .set(&DataKey::MissionCount, &(position + 1));
+
+    let owned = owner_count(env, owner);
+    storage::write(env, &DataKey::OwnerMissionAt(owner.clone(), owned), mission_id);
+    storage::write(env, &DataKey::OwnerMissionCount(owner.clone()), &(owned + 1));
+}
+
+fn with_missions(env: &Env, ids: Vec<BytesN<32>>) -> Vec<(BytesN<32>, Mission)> {
+    let mut page = Vec::new(env);
+    for id in ids.iter() {
+        if let Ok(mission) = load_mission(env, &id) {
+            page.push_back((id, mission));
+        }
+    }
+    page
+}
+
+#[contractimpl]
+impl MissionFundContract {
+    pub fn mission_count(env: Env) -> u32 {
+        count(&env)
+    }
+
+    /// Missions in registration order, starting at index position `start`.
+    pub fn list_missions(env: Env, start: u32, limit: u32) -> Vec<(BytesN<32>, Mission)> {
+        let end = count(&env).min(start.saturating_add(limit.min(MAX_PAGE_SIZE)));
+        let mut ids = Vec::new(&env);
+        for position in start..end {
+            if let Some(id) = storage::read(&env, &DataKey::MissionAt(position)) {
+                ids.push_back(id);
+            }
+        }
+        with_missions(&env, ids)
+    }
+
+    /// Missions owned by `owner`, in registration order.
+    pub fn missions_by_owner(
+        env: Env,
+        owner: Address,
+        start: u32,
+        limit: u32,
+    ) -> Vec<(BytesN<32>, Mission)> {
+        let end = owner_count(&env, &owner).min(start.saturating_add(limit.min(MAX_PAGE_SIZE)));
+        let mut ids = Vec::new(&env);
+        for position in start..end {
+            if let Some(id) = storage::read(&env, &DataKey::OwnerMissionAt(owner.clone(), position)) {
+                ids.push_back(id);
+            }
+        }
+        with_missions(&env, ids)
+    }
+
+    /// Missions currently in `status`. Status depends on the ledger clock,
+    /// so it is evaluated on read by scanning the global index from `start`;
+    /// use the returned `next` cursor to continue.
+    pub fn missions_by_status(
+        env: Env,
+        status: MissionStatus,
+        start: u32,
+        limit: u32,
+    ) -> MissionPage {
+        let total = count(&env);
+        let limit = limit.min(MAX_PAGE_SIZE);
+        let scan_end = total.min(start.saturating_add(MAX_SCAN));
+        let mut missions = Vec::new(&env);
+        let mut position = start;
+        while position < scan_end && missions.len() < limit {
+            if let Some(id) = storage::read::<BytesN<32>>(&env, &DataKey::MissionAt(position)) {
+                if let Ok(mission) = load_mission(&env, &id) {
+                    if mission.status(&env) == status {
+                        missions.push_back((id, mission));
+                    }
+                }
+            }
+            position += 1;
+        }
+        MissionPage {
+            missions,
+            next: if position < total { Some(position) } else { None },
+        }
+    }
+}
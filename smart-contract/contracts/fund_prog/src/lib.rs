@@ -1,8 +1,68 @@
 #![no_std]
+// Entry points like register_mission take many arguments and the generated
+// client mirrors them, so the lint is silenced crate-wide.
+#![allow(clippy::too_many_arguments)]
 
-use soroban_sdk::{contract, contractimpl, contracttype, Env, Symbol, String, Address, Map, BytesN, Val, IntoVal};
+use soroban_sdk::{contract, contractimpl, contracttype, token, Env, Symbol, String, Address, Map, BytesN, Val, Vec, TryFromVal};
 use soroban_sdk::symbol_short;
 
+mod admin;
+mod asset;
+mod attestor;
+mod batch;
+mod callback;
+mod contribution;
+mod error;
+mod events;
+mod fee;
+mod hex;
+mod index;
+mod lifecycle;
+mod matching;
+mod math;
+mod milestone;
+mod nft;
+mod pledge;
+mod rarity;
+mod storage;
+mod team;
+mod upgrade;
+
+pub use admin::Role;
+pub use asset::AssetFunds;
+pub use attestor::{Attestor, AttestorClient};
+pub use callback::TargetCallback;
+pub use contribution::Contribution;
+pub use error::Error;
+pub use fee::FeeQuote;
+pub use index::MissionPage;
+pub use lifecycle::MetadataChange;
+pub use matching::{MatchingRound, RoundEntry};
+pub use milestone::{Milestone, MilestonePlan, MilestoneSpec, MilestoneStatus};
+pub use nft::Nft;
+pub use pledge::Pledge;
+pub use rarity::{Rarity, RarityThresholds};
+pub use storage::{DataKey, TtlConfig};
+pub use team::{Proposal, Team, TeamAction};
+
+use storage::LegacyMission;
+use upgrade::SCHEMA_VERSION;
+
+/// A crowdfunding campaign. Funding is all-or-nothing: donations are
+/// accepted until `deadline` (a ledger timestamp), the owner can only take
+/// the money out once `target_amount` is reached, and donors can claim
+/// refunds if the deadline passes first. `funded_amount` counts donations net
+/// of the platform fee; the fees themselves are tallied in `fees_paid`.
+///
+/// Amounts are in `reference_asset`. Donations in other accepted assets
+/// count at the conversion rate of the moment they are made; what the
+/// mission raised and holds in each asset is tracked in `funds`, whose keys
+/// are the accepted assets.
+///
+/// `status` is the owner-set part of the lifecycle (`Active`, `Closed` or
+/// `Cancelled`); `Funded` and `Expired` are derived on read. `refund_pool`
+/// holds the unspent funds donors share pro rata once a mission is
+/// cancelled.
 #[contracttype]
 #[derive(Clone)]
 pub struct Mission {
@@ -12,6 +72,202 @@ pub struct Mission {
     target_amount: i128,
     funded_amount: i128,
     owner: Address,
+    deadline: u64,
+    withdrawn: i128,
+    fees_paid: i128,
+    status: MissionStatus,
+    refund_pool: i128,
+    reference_asset: Address,
+    funds: Map<Address, AssetFunds>,
+}
+
+/// Where a mission stands in its funding campaign.
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MissionStatus {
+    /// Before the deadline and still short of the target.
+    Active,
+    /// Target reached; the owner can draw the funds.
+    Funded,
+    /// Deadline passed short of the target; donors can claim refunds.
+    Expired,
+    /// Owner stopped new funding; the campaign otherwise runs its course.
+    Closed,
+    /// Owner called the mission off; donors share the unspent funds.
+    Cancelled,
+}
+
+impl Mission {
+    fn target_reached(&self) -> bool {
+        self.funded_amount >= self.target_amount
+    }
+
+    fn status(&self, env: &Env) -> MissionStatus {
+        if self.status == MissionStatus::Cancelled {
+            MissionStatus::Cancelled
+        } else if self.target_reached() {
+            MissionStatus::Funded
+        } else if env.ledger().timestamp() > self.deadline {
+            MissionStatus::Expired
+        } else {
+            self.status
+        }
+    }
+
+    fn ensure_not_cancelled(&self) -> Result<(), Error> {
+        if self.status == MissionStatus::Cancelled {
+            return Err(Error::MissionCancelled);
+        }
+        Ok(())
+    }
+}
+
+fn load_mission(env: &Env, mission_id: &BytesN<32>) -> Result<Mission, Error> {
+    storage::read(env, &DataKey::Mission(mission_id.clone())).ok_or(Error::MissionNotFound)
+}
+
+fn save_mission(env: &Env, mission_id: &BytesN<32>, mission: &Mission) {
+    storage::write(env, &DataKey::Mission(mission_id.clone()), mission);
+}
+
+/// Loads a mission that can take donations right now.
+fn open_for_funding(env: &Env, mission_id: &BytesN<32>) -> Result<Mission, Error> {
+    admin::ensure_live(env, mission_id)?;
+    upgrade::ensure_current(env)?;
+    let mission = load_mission(env, mission_id)?;
+    match mission.status {
+        MissionStatus::Closed => return Err(Error::MissionClosed),
+        MissionStatus::Cancelled => return Err(Error::MissionCancelled),
+        _ => {}
+    }
+    if env.ledger().timestamp() > mission.deadline {
+        return Err(Error::DeadlinePassed);
+    }
+    // Once the last milestone is paid out, escrow has nothing left to
+    // release new donations through.
+    if milestone::all_completed(env, mission_id) {
+        return Err(Error::MilestonesCompleted);
+    }
+    Ok(mission)
+}
+
+/// How the tokens of a donation reach the contract.
+#[derive(Clone, Copy)]
+enum Payment {
+    /// Transferred from the donor, who authorized the call.
+    Transfer,
+    /// Pulled through an allowance the donor granted this contract.
+    Allowance,
+    /// Already transferred by the caller, net and fee alike.
+    Prepaid,
+}
+
+/// Takes a donation paid as `payment` says and mints the donor's
+/// contribution NFT.
+fn accept_donation(
+    env: &Env,
+    mission_id: &BytesN<32>,
+    donor: &Address,
+    asset: &Address,
+    amount: i128,
+    payment: Payment,
+) -> Result<u64, Error> {
+    if amount <= 0 {
+        return Err(Error::InvalidAmount);
+    }
+
+    let mut mission = open_for_funding(env, mission_id)?;
+    attestor::ensure_verified(env, mission_id, donor)?;
+    let mut funds = mission.funds.get(asset.clone()).ok_or(Error::UnsupportedAsset)?;
+
+    // Totals are checked before any tokens move, so a donation that
+    // would overflow them fails cleanly.
+    let (quote, collector) = fee::quote(env, amount)?;
+    let value = asset::to_reference(env, quote.net, asset, &mission.reference_asset)?;
+    // A donation that rounds down to nothing in the reference asset would
+    // count for nothing and could never be refunded.
+    if value <= 0 {
+        return Err(Error::InvalidAmount);
+    }
+    let fee_value = asset::to_reference(env, quote.fee, asset, &mission.reference_asset)?;
+    let was_reached = mission.target_reached();
+    funds.raised = math::add(funds.raised, quote.net)?;
+    funds.held = math::add(funds.held, quote.net)?;
+    mission.funds.set(asset.clone(), funds);
+    mission.funded_amount = math::add(mission.funded_amount, value)?;
+    mission.fees_paid = math::add(mission.fees_paid, fee_value)?;
+
+    // Pull the donation into contract custody so the mission's holdings
+    // always match what the contract actually holds. The fee share goes
+    // straight to the collector.
+    let token = token::Client::new(env, asset);
+    let pull = |to: &Address, amount: i128| match payment {
+        Payment::Transfer => token.transfer(donor, to, &amount),
+        Payment::Allowance => {
+            token.transfer_from(&env.current_contract_address(), donor, to, &amount)
+        }
+        Payment::Prepaid => {}
+    };
+    if let Some(collector) = collector.filter(|_| quote.fee > 0) {
+        pull(&collector, quote.fee);
+        events::fee_charged(env, mission_id, donor, quote.fee);
+    }
+    pull(&env.current_contract_address(), quote.net);
+    save_mission(env, mission_id, &mission);
+    if *asset != mission.reference_asset {
+        events::funded_in_asset(env, mission_id, donor, asset, quote.net);
+    }
+    events::funded(env, mission_id, donor, value, mission.funded_amount);
+    let reached_now = !was_reached && mission.target_reached();
+    if reached_now {
+        events::target_reached(env, mission_id, mission.target_amount, mission.funded_amount);
+    }
+
+    let contribution = contribution::record(
+        env,
+        mission_id,
+        donor,
+        &mission.reference_asset,
+        asset,
+        quote.net,
+        value,
+    );
+
+    matching::record(env, mission_id, donor, asset, quote.net)?;
+
+    let rarity = rarity::tier(env, contribution.amount, mission.target_amount);
+    let token_id = nft::mint_nft(env, donor, mission_id, &mission, asset, quote.net, rarity);
+    rarity::track(env, mission_id, donor, &contribution, value, mission.target_amount);
+    if reached_now {
+        // Batches leave the call pending: the partner's own ledger writes
+        // would count against the batch's write budget.
+        let inline = !matches!(payment, Payment::Prepaid);
+        callback::target_reached(env, mission_id, &mission, inline);
+    }
+    Ok(token_id)
+}
+
+/// Pays everything withdrawable to `to`; see `withdraw`.
+fn withdraw_to(env: &Env, mission_id: &BytesN<32>, to: &Address) -> Result<i128, Error> {
+    let mut mission = load_mission(env, mission_id)?;
+    admin::ensure_live(env, mission_id)?;
+    mission.ensure_not_cancelled()?;
+    if !mission.target_reached() {
+        return Err(Error::NotFunded);
+    }
+    if storage::has(env, &DataKey::Milestones(mission_id.clone())) {
+        return Err(Error::FundsInEscrow);
+    }
+
+    let amount = mission.funded_amount - mission.withdrawn;
+    if amount == 0 {
+        return Err(Error::NothingToClaim);
+    }
+    mission.withdrawn += amount;
+    asset::pay_out(env, &mut mission, amount, amount, to)?;
+    save_mission(env, mission_id, &mission);
+    events::withdrawn(env, mission_id, to, amount);
+    Ok(amount)
 }
 
 #[contract]
@@ -19,6 +275,134 @@ pub struct MissionFundContract;
 
 #[contractimpl]
 impl MissionFundContract {
+    /// One-time setup: records the admin and the Stellar Asset Contract that
+    /// donations are denominated in.
+    pub fn initialize(env: Env, admin: Address, token: Address) -> Result<(), Error> {
+        if env.storage().instance().has(&DataKey::Token) {
+            return Err(Error::AlreadyInitialized);
+        }
+        admin.require_auth();
+        env.storage().instance().set(&DataKey::Admin, &admin);
+        env.storage().instance().set(&DataKey::Token, &token);
+        env.storage()
+            .instance()
+            .set(&DataKey::SchemaVersion, &SCHEMA_VERSION);
+        storage::bump_instance(&env);
+        Ok(())
+    }
+
+    pub fn get_ttl_config(env: Env) -> TtlConfig {
+        storage::ttl_config(&env)
+    }
+
+    /// Admin override of the lifetimes applied on every read and write.
+    /// `*_extend_to` may not exceed the network's maximum entry TTL.
+    pub fn set_ttl_config(env: Env, config: TtlConfig) -> Result<(), Error> {
+        admin::require_admin(&env)?;
+        let max_ttl = env.storage().max_ttl();
+        if config.persistent_threshold >= config.persistent_extend_to
+            || config.instance_threshold >= config.instance_extend_to
+            || config.persistent_extend_to > max_ttl
+            || config.instance_extend_to > max_ttl
+        {
+            return Err(Error::InvalidConfig);
+        }
+        env.storage().instance().set(&DataKey::TtlConfig, &config);
+        storage::bump_instance(&env);
+        Ok(())
+    }
+
+    /// Extends the lifetime of a mission's own records. Anyone can pay for
+    /// this to keep a long-running mission from being archived; records kept
+    /// per donor or per proposal are extended a page at a time by
+    /// `bump_donors` and `bump_proposals`. Entries that were already archived
+    /// must first be restored with a `RestoreFootprint` operation, after
+    /// which this brings them back to the full TTL.
+    pub fn bump_mission(env: Env, mission_id: BytesN<32>) -> Result<(), Error> {
+        let key = DataKey::Mission(mission_id.clone());
+        if !storage::has(&env, &key) {
+            return Err(Error::MissionNotFound);
+        }
+        storage::extend(&env, &key);
+        for key in [
+            DataKey::Milestones(mission_id.clone()),
+            DataKey::MissionPaused(mission_id.clone()),
+            DataKey::MetadataChanges(mission_id.clone()),
+            DataKey::DonorCount(mission_id.clone()),
+            DataKey::MissionRounds(mission_id.clone()),
+            DataKey::Team(mission_id.clone()),
+            DataKey::ProposalCount(mission_id.clone()),
+            DataKey::Attestor(mission_id.clone()),
+            DataKey::TargetCallback(mission_id.clone()),
+            DataKey::PendingCallback(mission_id),
+        ] {
+            storage::extend_if_present(&env, &key);
+        }
+        storage::bump_instance(&env);
+        Ok(())
+    }
+
+    pub fn get_token(env: Env) -> Result<Address, Error> {
+        asset::platform_token(&env)
+    }
+
+    /// Moves missions written by the pre-`DataKey` contract (stored under
+    /// the bare mission id, with their NFT under `sha256(mission_id)`) into
+    /// the typed layout. The legacy NFT is re-issued as a registry token
+    /// owned by its recorded donor, with metadata in the current schema.
+    /// Ids with no legacy entry are skipped, so each mission is migrated at
+    /// most once. Legacy missions had no deadline, so they all take
+    /// `deadline`, which must be in the future; past it they expire into
+    /// refunds like any other. Their recorded totals were never backed by
+    /// tokens, so they restart from zero; the old figure is kept in the
+    /// `migrate` event. Returns the number migrated.
+    pub fn migrate_legacy_missions(
+        env: Env,
+        mission_ids: Vec<BytesN<32>>,
+        deadline: u64,
+    ) -> Result<u32, Error> {
+        admin::require_admin(&env)?;
+        upgrade::ensure_current(&env)?;
+        if deadline <= env.ledger().timestamp() {
+            return Err(Error::InvalidDeadline);
+        }
+
+        let legacy = env.storage().persistent();
+        let mut migrated = 0;
+        for mission_id in mission_ids.iter() {
+            let Some(mission) = legacy.get::<_, LegacyMission>(&mission_id) else {
+                continue;
+            };
+            let legacy_funded = mission.funded_amount;
+            let mission = upgrade::from_v3(&env, &mission_id, mission.into_v3(deadline))?;
+            index::add(&env, &mission_id, &mission.owner);
+            save_mission(&env, &mission_id, &mission);
+            events::mission_migrated(&env, &mission_id, legacy_funded);
+            legacy.remove(&mission_id);
+
+            let legacy_nft_key = env.crypto().sha256(&mission_id.clone().into()).to_bytes();
+            if let Some(metadata) = legacy.get::<_, Map<Symbol, Val>>(&legacy_nft_key) {
+                let owner = metadata
+                    .get(symbol_short!("owner"))
+                    .and_then(|v| Address::try_from_val(&env, &v).ok());
+                let amount = metadata
+                    .get(symbol_short!("contrib"))
+                    .and_then(|v| i128::try_from_val(&env, &v).ok())
+                    .unwrap_or(0);
+                if let Some(owner) = owner {
+                    let asset = &mission.reference_asset;
+                    let rarity = rarity::tier(&env, amount, mission.target_amount);
+                    nft::mint_nft(&env, &owner, &mission_id, &mission, asset, amount, rarity);
+                }
+                legacy.remove(&legacy_nft_key);
+            }
+            migrated += 1;
+        }
+        Ok(migrated)
+    }
+
+    /// Registers a mission owned by `owner`. With an `attestor`, a contract
+    /// implementing `Attestor`, only donors it vouches for may fund it.
     pub fn register_mission(
         env: Env,
         mission_id: BytesN<32>,
@@ -26,8 +410,26 @@ impl MissionFundContract {
         desc: String,
         image_url: String,
         target_amount: i128,
+        deadline: u64,
         owner: Address,
-    ) {
+        milestones: Vec<MilestoneSpec>,
+        reviewer: Option<Address>,
+        attestor: Option<Address>,
+    ) -> Result<(), Error> {
+        owner.require_auth();
+        upgrade::ensure_current(&env)?;
+        if target_amount <= 0 {
+            return Err(Error::InvalidAmount);
+        }
+        if deadline <= env.ledger().timestamp() {
+            return Err(Error::InvalidDeadline);
+        }
+        let key = DataKey::Mission(mission_id.clone());
+        // A bare-id entry is a mission that has not been migrated yet.
+        if storage::has(&env, &key) || env.storage().persistent().has(&mission_id) {
+            return Err(Error::AlreadyExists);
+        }
+        let token = asset::platform_token(&env)?;
         let mission = Mission {
             title,
             desc,
@@ -35,262 +437,126 @@ impl MissionFundContract {
             target_amount,
             funded_amount: 0,
             owner,
+            deadline,
+            withdrawn: 0,
+            fees_paid: 0,
+            status: MissionStatus::Active,
+            refund_pool: 0,
+            funds: asset::initial_funds(&env, &token),
+            reference_asset: token,
         };
-        env.storage().persistent().set(&mission_id, &mission);
+        milestone::create_plan(&env, &mission_id, milestones, reviewer)?;
+        if let Some(attestor) = attestor {
+            storage::write(&env, &DataKey::Attestor(mission_id.clone()), &attestor);
+        }
+        storage::write(&env, &key, &mission);
+        index::add(&env, &mission_id, &mission.owner);
+        events::mission_registered(&env, &mission_id, &mission.owner, target_amount, deadline);
+        Ok(())
     }
 
+    /// Donates `amount` of the mission's reference asset and mints the
+    /// donor a contribution NFT, whose token id is returned.
     pub fn fund_mission(
         env: Env,
         mission_id: BytesN<32>,
         donor: Address,
         amount: i128,
-    ) {
-        let mut mission: Mission = env
-            .storage()
-            .persistent()
-            .get(&mission_id)
-            .expect("Mission not found");
+    ) -> Result<u64, Error> {
+        upgrade::ensure_current(&env)?;
+        let asset = load_mission(&env, &mission_id)?.reference_asset;
+        Self::fund_mission_with(env, mission_id, donor, asset, amount)
+    }
 
-        mission.funded_amount += amount;
-        env.storage().persistent().set(&mission_id, &mission);
+    /// Same as `fund_mission` in any asset the mission accepts. The donation
+    /// counts towards the target at the current conversion rate.
+    pub fn fund_mission_with(
+        env: Env,
+        mission_id: BytesN<32>,
+        donor: Address,
+        asset: Address,
+        amount: i128,
+    ) -> Result<u64, Error> {
+        donor.require_auth();
+        accept_donation(&env, &mission_id, &donor, &asset, amount, Payment::Transfer)
+    }
 
-        Self::mint_nft(env, donor, mission_id.clone(), amount);
+    /// Returns `donor`'s full contribution (net of the platform fee, which is
+    /// not refundable) once the deadline has passed without the target being
+    /// reached. For a cancelled mission donors instead get their pro-rata
+    /// share of the funds the owner had not yet received, at any time. Each
+    /// asset is refunded in kind; the returned amount is the reference value.
+    /// Anyone may trigger it; the funds only ever go back to the donor.
+    pub fn claim_refund(env: Env, mission_id: BytesN<32>, donor: Address) -> Result<i128, Error> {
+        upgrade::ensure_current(&env)?;
+        let mut mission = load_mission(&env, &mission_id)?;
+        let cancelled = mission.status == MissionStatus::Cancelled;
+        if !cancelled
+            && (env.ledger().timestamp() <= mission.deadline || mission.target_reached())
+        {
+            return Err(Error::RefundUnavailable);
+        }
+
+        let mut contribution =
+            contribution::load(&env, &mission_id, &donor).ok_or(Error::NotContributor)?;
+        let contributed = contribution.amount;
+        if contributed == 0 {
+            return Err(Error::NothingToClaim);
+        }
+        // Flooring each share only ever leaves more for later claims, and the
+        // last donor's share is the whole remaining pool.
+        let amount = if cancelled {
+            math::mul_div(contributed, mission.refund_pool, mission.funded_amount)?
+        } else {
+            contributed
+        };
+        let assets = contribution::assets(&env, &mission_id, &donor, &mission.reference_asset);
+        contribution.amount = 0;
+        contribution.refunded += amount;
+        contribution::save(&env, &mission_id, &donor, &contribution);
+        contribution::save_assets(&env, &mission_id, &donor, &Map::new(&env));
+        mission.funded_amount -= contributed;
+        if cancelled {
+            mission.refund_pool -= amount;
+        }
+        asset::refund(&env, &mut mission, &assets, &donor)?;
+        matching::forget(&env, &mission_id, &donor);
+        save_mission(&env, &mission_id, &mission);
+        events::refunded(&env, &mission_id, &donor, amount);
+        Ok(amount)
     }
 
-    fn mint_nft(env: Env, donor: Address, mission_id: BytesN<32>, amount: i128) {
-        let token_id = env.crypto().sha256(&mission_id.clone().into());
-        let mission: Mission = env
-            .storage()
-            .persistent()
-            .get(&mission_id)
-            .expect("Mission not found");
-
-        // Construct metadata URI: ipfs://astrofi-nft/mission/<mission_id_hex>/metadata.json
-        // Convert mission_id to hex string (simplified as string representation)
-        // let mission_id_str = u64(mission_id); // Placeholder for hex encoding
-        // let metadata_uri = String::from_str(
-        //     &env,
-        //     &["ipfs://astrofi-nft/mission/", &mission_id_str, "/metadata.json"].concat(),
-        // );
-
-        let mut nft_info: Map<Symbol, Val> = Map::new(&env);
-        nft_info.set(symbol_short!("owner"), donor.into_val(&env));
-        nft_info.set(symbol_short!("title"), mission.title.into_val(&env));
-        nft_info.set(symbol_short!("desc"), mission.desc.into_val(&env));
-        nft_info.set(symbol_short!("image"), mission.image_url.into_val(&env));
-        nft_info.set(symbol_short!("contrib"),  amount.into_val(&env));
-        nft_info.set(symbol_short!("missionID"), mission_id.into_val(&env));
-        // nft_info.set(symbol_short!("metadata"), metadata_uri.into_val(&env));
-
-        env.storage().persistent().set(&token_id, &nft_info);
+    /// Pays everything raised and not yet withdrawn, in every asset, to the
+    /// owner and returns its reference value. Only possible once the target
+    /// is reached; missions with milestones release funds through
+    /// `review_milestone` instead.
+    /// Team-owned missions withdraw through a `TeamAction::Withdraw`
+    /// proposal instead.
+    pub fn withdraw(env: Env, mission_id: BytesN<32>) -> Result<i128, Error> {
+        upgrade::ensure_current(&env)?;
+        let mission = load_mission(&env, &mission_id)?;
+        mission.owner.require_auth();
+        team::ensure_solo(&env, &mission_id)?;
+        withdraw_to(&env, &mission_id, &mission.owner)
     }
 
-    pub fn get_nft_metadata(env: Env, mission_id: BytesN<32>) -> Map<Symbol, Val> {
-        let token_id = env.crypto().sha256(&mission_id.into());
-        env.storage()
-            .persistent()
-            .get(&token_id)
-            .expect("NFT not found")
+    /// What is still missing to reach the target, in the mission's
+    /// reference asset.
+    pub fn get_remaining_funds(env: Env, mission_id: BytesN<32>) -> Result<i128, Error> {
+        upgrade::ensure_current(&env)?;
+        let mission = load_mission(&env, &mission_id)?;
+        Ok(mission.target_amount - mission.funded_amount)
     }
 
-    pub fn get_remaining_funds(env: Env, mission_id: BytesN<32>) -> i128 {
-        let mission: Mission = env
-            .storage()
-            .persistent()
-            .get(&mission_id)
-            .expect("Mission not found");
-        mission.target_amount - mission.funded_amount
+    pub fn get_mission(env: Env, mission_id: BytesN<32>) -> Result<Mission, Error> {
+        upgrade::ensure_current(&env)?;
+        load_mission(&env, &mission_id)
     }
 
-    pub fn get_mission(env: Env, mission_id: BytesN<32>) -> Mission {
-        env.storage()
-            .persistent()
-            .get(&mission_id)
-            .expect("Mission not found")
+    pub fn get_mission_status(env: Env, mission_id: BytesN<32>) -> Result<MissionStatus, Error> {
+        upgrade::ensure_current(&env)?;
+        Ok(load_mission(&env, &mission_id)?.status(&env))
     }
 }
 
-
-
-
-
-
-// #![no_std]
-// use soroban_sdk::{contract, contractimpl, Address, vec, Symbol, Contracttype, Env, String, Vec};
-
-// pub struct MintNFT;
-
-// #[contract]
-// pub struct SpaceMission {
-//     pub title: String,
-//     pub desc: String,
-//     pub image_url: String,
-//     pub project_id: u32,
-//     pub amount: u64,
-// };
-
-
-// #[contractimpl]
-// impl MintNFT {
-//     pub fn initialize(env: Env, admin: Address) {
-//         admin.require_auth();
-//         env.storage().set(Symbol::new(&env, "admin"), &admin);
-//     }
-
-
-//     pub fn fund_mission(env: Env, title: String, description: String, amount: u64, project_id: u32) {
-//         let admin: Address = env.storage().get(Symbol::new(&env, "admin")).unwrap();
-//         admin.require_auth();
-//         let mut count_pass: u64 = env.storage().instance().get(&COUNT_PASS).unwrap_or(0);
-//         count_pass += 1;
-
-//         let mission = SpaceMission {
-//             title: title,
-//             description: description,
-//             image_url: String::from_str(&env, "https://example.com/image.png"),
-//             project_id: ,
-//             amount: amount,
-//         };
-
-//         env.storage().set(Symbol::new(&env, "Funding Successfully"), &mission);
-//     }
-
-    
-//     pub fn mint_nft(env: Env, project_id: u32) {
-//         let admin: Address = env.storage().get(Symbol::new(&env, "admin")).unwrap();
-//         admin.require_auth();
-//         let mut count_pass: u64 = env.storage().instance().get(&COUNT_PASS).unwrap_or(0);
-//         count_pass += 1;
-
-//         let mission: SpaceMission = env.storage().get(Symbol::new(&env, "mission")).unwrap();
-//         if mission.project_id == project_id {
-//             let reward_nft= {
-//                 project_id: project_id,
-//                 title: mission.title.clone(),
-//                 description: mission.description.clone(),
-//                 image_url: mission.image_url.clone(),
-//                 attributes: vec![
-//                     owner's wallet address,
-//                     amount
-//                 ],
-               
-//             }
-//             env.storage().set(Symbol::new(&env, "nft_minted"), &true);
-//         } else {
-//             panic!("Project ID does not match the mission.");
-//         }
-//     }
-
-//     pub fn mint_nft(env: Env, project_id: u32) {
-//         let admin: Address = env.storage().get(Symbol::new(&env, "admin")).unwrap();
-//         admin.require_auth();
-//         let mut count_pass: u64 = env.storage().instance().get(&COUNT_PASS).unwrap_or(0);
-//         count_pass += 1;
-
-//         let mission: SpaceMission = env.storage().get(Symbol::new(&env, "mission")).unwrap();
-//         if mission.project_id == project_id {
-//             let reward_nft= {
-//                 project_id: project_id,
-//                 title: mission.title.clone(),
-//                 description: mission.description.clone(),
-//                 image_url: mission.image_url.clone(),
-//                 attributes: vec![
-//                     owner's wallet address,
-//                     project_id
-//                 ],
-               
-//             }
-//             env.storage().set(Symbol::new(&env, "nft_minted"), &true);
-//         } else {
-//             panic!("Token ID does not match the mission.");
-//         }
-//     }
-
-//     //track remaining fund required after contributions
-//     pub fn track_remaining_fund(env: Env, project_id: u32) {
-//         let admin: Address = env.storage().get(Symbol::new(&env, "admin")).unwrap();
-//         admin.require_auth();
-
-//         let mission: SpaceMission = env.storage().get(Symbol::new(&env, "mission")).unwrap();
-//         if mission.project_id == project_id {
-//             let remaining_fund = mission.amount - funding_amount;
-//             env.storage().set(Symbol::new(&env, "Required "), &remaining_fund);
-//         } else {
-//             panic!("Project ID does not match the mission.");
-//         }
-//     }
-
-//     pub fn mint_nft(env: Env, donor: Address, mission_id: Bytes) {
-//         let token_id = env.crypto().sha256(&mission_id);
-//         let metadata_uri = format!("ipfs://{}/metadata.json", mission_id.to_string()).into_val(&env);
-
-//         let nft_info = Map::new(&env)
-//             .set(Symbol::short("owner"), donor)
-//             .set(Symbol::short("metadata"), metadata_uri);
-
-//         env.storage().set(&token_id, &nft_info);
-//     }
-
-//     pub fn get_nft_metadata(env: Env, token_id: Bytes) -> Map<Symbol, Address> {
-//         env.storage().get_unchecked(&token_id).unwrap()
-//     }
-    
-// }
-
-
-// #![no_std]
-
-// use soroban_sdk::{contractimpl, Address, Env, Symbol, Bytes, Map, BytesN, IntoVal};
-
-// pub struct FundLockerContract;
-
-// #[derive(Clone)]
-// pub struct Mission {
-//     owner: Address,
-//     target_amount: i128,
-//     funded_amount: i128,
-//     milestones: Vec<Symbol>,
-//     completed_milestones: Vec<bool>,
-// }
-
-// #[contractimpl]
-// impl FundLockerContract {
-//     pub fn init_mission(env: Env, mission_id: BytesN<32>, owner: Address, target_amount: i128) {
-//         let mission = Mission {
-//             owner,
-//             target_amount,
-//             funded_amount: 0,
-//         };
-//         env.storage().set(&mission_id, &mission);
-//     }
-
-//     pub fn fund_mission(env: Env, mission_id: BytesN<32>, funder: Address, amount: i128) {
-//         let mut mission: Mission = env.storage().get_unchecked(&mission_id).unwrap();
-//         mission.funded_amount += amount;
-//         env.storage().set(&mission_id, &mission);
-
-//         // Actual payment flow handled outside (e.g., in backend) to Stellar account
-//     }
-
-//     pub fn complete_milestone(env: Env, mission_id: BytesN<32>, milestone_index: u32) {
-//         let mut mission: Mission = env.storage().get_unchecked(&mission_id).unwrap();
-//         mission.completed_milestones[milestone_index as usize] = true;
-//         env.storage().set(&mission_id, &mission);
-//     }
-
-//     pub fn release_funds(env: Env, mission_id: BytesN<32>) -> i128 {
-//         let mission: Mission = env.storage().get_unchecked(&mission_id).unwrap();
-//         let completed = mission.completed_milestones.iter().filter(|&&m| m).count();
-//         let milestone_count = mission.milestones.len();
-
-//         let percentage_complete = (completed as i128 * 100) / (milestone_count as i128);
-
-//         // Only partial funds can be released based on completed milestones
-//         let release_amount = (mission.funded_amount * percentage_complete) / 100;
-//         release_amount
-//     }
-// }
-
-
-
-// mod test;
+mod test;
@@ -0,0 +1,137 @@
+use soroban_sdk::{contractimpl, contracttype, BytesN, Env, String, Vec};
+
+use crate::storage::{self, DataKey};
+use crate::{contribution, events, load_mission, milestone, save_mission, team, upgrade, Error, MissionFundContract, MissionFundContractArgs, MissionFundContractClient, MissionStatus};
+
+/// Upper bound on metadata edits recorded for a mission after its first
+/// donation, so the change log stays a bounded entry.
+pub const MAX_METADATA_CHANGES: u32 = 20;
+
+/// Metadata a mission carried before an edit made after donors had
+/// committed to it, and when it was replaced.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MetadataChange {
+    pub title: String,
+    pub desc: String,
+    pub image_url: String,
+    pub changed_at: u64,
+}
+
+fn changes(env: &Env, mission_id: &BytesN<32>) -> Vec<MetadataChange> {
+    storage::read(env, &DataKey::MetadataChanges(mission_id.clone())).unwrap_or(Vec::new(env))
+}
+
+/// Replaces a mission's metadata, logging the old values once donations
+/// exist; see `update_mission_metadata`.
+pub(crate) fn update_metadata(
+    env: &Env,
+    mission_id: &BytesN<32>,
+    title: String,
+    desc: String,
+    image_url: String,
+) -> Result<(), Error> {
+    let mut mission = load_mission(env, mission_id)?;
+    mission.ensure_not_cancelled()?;
+
+    if contribution::donor_count(env, mission_id) > 0 {
+        let mut log = changes(env, mission_id);
+        if log.len() >= MAX_METADATA_CHANGES {
+            return Err(Error::TooManyChanges);
+        }
+        log.push_back(MetadataChange {
+            title: mission.title,
+            desc: mission.desc,
+            image_url: mission.image_url,
+            changed_at: env.ledger().timestamp(),
+        });
+        storage::write(env, &DataKey::MetadataChanges(mission_id.clone()), &log);
+    }
+
+    events::mission_updated(env, mission_id, &title, &desc, &image_url);
+    mission.title = title;
+    mission.desc = desc;
+    mission.image_url = image_url;
+    save_mission(env, mission_id, &mission);
+    Ok(())
+}
+
+/// Stops new donations; see `close_mission`.
+pub(crate) fn close(env: &Env, mission_id: &BytesN<32>) -> Result<(), Error> {
+    let mut mission = load_mission(env, mission_id)?;
+    match mission.status {
+        MissionStatus::Closed => return Err(Error::MissionClosed),
+        MissionStatus::Cancelled => return Err(Error::MissionCancelled),
+        _ => {}
+    }
+    mission.status = MissionStatus::Closed;
+    save_mission(env, mission_id, &mission);
+    events::mission_closed(env, mission_id);
+    Ok(())
+}
+
+/// Calls the mission off and returns the refund pool; see
+/// `cancel_mission`.
+pub(crate) fn cancel(env: &Env, mission_id: &BytesN<32>) -> Result<i128, Error> {
+    let mut mission = load_mission(env, mission_id)?;
+    mission.ensure_not_cancelled()?;
+
+    let unspent =
+        mission.funded_amount - mission.withdrawn - milestone::released(env, mission_id);
+    mission.status = MissionStatus::Cancelled;
+    mission.refund_pool = unspent;
+    save_mission(env, mission_id, &mission);
+    events::mission_cancelled(env, mission_id, unspent);
+    Ok(unspent)
+}
+
+#[contractimpl]
+impl MissionFundContract {
+    /// Replaces a mission's title, description and image. Free to edit
+    /// until the first donation; after that every edit appends the previous
+    /// values to the change log, up to `MAX_METADATA_CHANGES` edits.
+    /// Team-owned missions edit through a `TeamAction::UpdateMetadata`
+    /// proposal instead.
+    pub fn update_mission_metadata(
+        env: Env,
+        mission_id: BytesN<32>,
+        title: String,
+        desc: String,
+        image_url: String,
+    ) -> Result<(), Error> {
+        upgrade::ensure_current(&env)?;
+        let mission = load_mission(&env, &mission_id)?;
+        mission.owner.require_auth();
+        team::ensure_solo(&env, &mission_id)?;
+        update_metadata(&env, &mission_id, title, desc, image_url)
+    }
+
+    /// Edits made after the first donation, oldest first.
+    pub fn get_metadata_changes(env: Env, mission_id: BytesN<32>) -> Vec<MetadataChange> {
+        changes(&env, &mission_id)
+    }
+
+    /// Stops new donations. Everything else carries on: a funded mission can
+    /// still pay out and an unfunded one still expires into refunds.
+    /// Team-owned missions close through a `TeamAction::Close` proposal.
+    pub fn close_mission(env: Env, mission_id: BytesN<32>) -> Result<(), Error> {
+        upgrade::ensure_current(&env)?;
+        let mission = load_mission(&env, &mission_id)?;
+        mission.owner.require_auth();
+        team::ensure_solo(&env, &mission_id)?;
+        close(&env, &mission_id)
+    }
+
+    /// Calls the mission off for good. Funds the owner has not received,
+    /// through `withdraw` or approved milestones, become a refund pool that
+    /// donors claim pro rata to their contribution via `claim_refund`.
+    /// Returns the size of the pool. Team-owned missions cancel through a
+    /// `TeamAction::Cancel` proposal.
+    pub fn cancel_mission(env: Env, mission_id: BytesN<32>) -> Result<i128, Error> {
+        upgrade::ensure_current(&env)?;
+        let mission = load_mission(&env, &mission_id)?;
+        mission.owner.require_auth();
+        team::ensure_solo(&env, &mission_id)?;
+        cancel(&env, &mission_id)
+    }
+}
@@ -0,0 +1,342 @@
+use soroban_sdk::{contractimpl, contracttype, token, Address, BytesN, Env, Vec};
+
+use crate::storage::{self, DataKey};
+use crate::{admin, asset, events, load_mission, math, milestone, team, upgrade, Error, Mission, MissionFundContract, MissionFundContractArgs, MissionFundContractClient, MissionStatus};
+
+/// Upper bound on missions in one round, so `finalize_round` stays within
+/// the budget of a single transaction: each mission costs two ledger reads
+/// and a write.
+pub const MAX_ROUND_MISSIONS: u32 = 15;
+
+/// A sponsor's matching pool, in the platform token, split across the
+/// missions that joined it by quadratic funding once the round window
+/// (`start` to `end`, ledger timestamps) is over.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MatchingRound {
+    pub sponsor: Address,
+    pub pool: i128,
+    pub start: u64,
+    pub end: u64,
+    pub missions: Vec<BytesN<32>>,
+    pub finalized: bool,
+}
+
+/// A mission's standing in a round. `total` is what its `donors` gave
+/// during the window, valued in the platform token, and `sqrt_sum` the sum
+/// of the square roots of each donor's total. `matched` is set when the
+/// round is finalized; `paid` is how much of it has been paid out since,
+/// to the owner or back to the sponsor.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RoundEntry {
+    pub donors: u32,
+    pub total: i128,
+    pub sqrt_sum: i128,
+    pub matched: i128,
+    pub paid: i128,
+}
+
+fn load_round(env: &Env, round_id: u32) -> Result<MatchingRound, Error> {
+    storage::read(env, &DataKey::Round(round_id)).ok_or(Error::RoundNotFound)
+}
+
+fn load_entry(env: &Env, round_id: u32, mission_id: &BytesN<32>) -> Result<RoundEntry, Error> {
+    storage::read(env, &DataKey::RoundMission(round_id, mission_id.clone()))
+        .ok_or(Error::RoundNotFound)
+}
+
+fn save_entry(env: &Env, round_id: u32, mission_id: &BytesN<32>, entry: &RoundEntry) {
+    storage::write(env, &DataKey::RoundMission(round_id, mission_id.clone()), entry);
+}
+
+fn mission_rounds(env: &Env, mission_id: &BytesN<32>) -> Vec<u32> {
+    storage::read(env, &DataKey::MissionRounds(mission_id.clone())).unwrap_or(Vec::new(env))
+}
+
+/// Rounds of the mission still collecting donations right now.
+fn open_rounds(env: &Env, mission_id: &BytesN<32>) -> Vec<u32> {
+    let now = env.ledger().timestamp();
+    let mut open = Vec::new(env);
+    for round_id in mission_rounds(env, mission_id).iter() {
+        if let Ok(round) = load_round(env, round_id) {
+            if !round.finalized && round.start <= now && now <= round.end {
+                open.push_back(round_id);
+            }
+        }
+    }
+    open
+}
+
+/// How many rounds the mission is in whose window is open.
+pub(crate) fn open_round_count(env: &Env, mission_id: &BytesN<32>) -> u32 {
+    open_rounds(env, mission_id).len()
+}
+
+/// Counts a donation of `amount` in `asset` towards every round the
+/// mission is in whose window is open.
+pub(crate) fn record(
+    env: &Env,
+    mission_id: &BytesN<32>,
+    donor: &Address,
+    asset: &Address,
+    amount: i128,
+) -> Result<(), Error> {
+    let rounds = open_rounds(env, mission_id);
+    if rounds.is_empty() {
+        return Ok(());
+    }
+    let value = asset::to_reference(env, amount, asset, &asset::platform_token(env)?)?;
+    for round_id in rounds.iter() {
+        let key = DataKey::RoundDonation(round_id, mission_id.clone(), donor.clone());
+        let given: i128 = storage::read(env, &key).unwrap_or(0);
+        let now_given = math::add(given, value)?;
+        let mut entry = load_entry(env, round_id, mission_id)?;
+        if given == 0 {
+            entry.donors += 1;
+        }
+        entry.total = math::add(entry.total, value)?;
+        entry.sqrt_sum = math::add(entry.sqrt_sum - math::sqrt(given), math::sqrt(now_given))?;
+        storage::write(env, &key, &now_given);
+        save_entry(env, round_id, mission_id, &entry);
+    }
+    Ok(())
+}
+
+/// Drops a refunded donor from every round of the mission not yet
+/// finalized, so money they took back is not matched. Finalized rounds need
+/// no adjusting: donors only get refunds from missions that expired or were
+/// cancelled, whose match goes back to the sponsor.
+pub(crate) fn forget(env: &Env, mission_id: &BytesN<32>, donor: &Address) {
+    for round_id in mission_rounds(env, mission_id).iter() {
+        if load_round(env, round_id).map_or(true, |round| round.finalized) {
+            continue;
+        }
+        let key = DataKey::RoundDonation(round_id, mission_id.clone(), donor.clone());
+        let Some(given) = storage::read::<i128>(env, &key) else {
+            continue;
+        };
+        if let Ok(mut entry) = load_entry(env, round_id, mission_id) {
+            entry.donors -= 1;
+            entry.total -= given;
+            entry.sqrt_sum -= math::sqrt(given);
+            save_entry(env, round_id, mission_id, &entry);
+        }
+        env.storage().persistent().remove(&key);
+    }
+}
+
+/// Enters the mission into a round; see `join_round`.
+pub(crate) fn join(env: &Env, round_id: u32, mission_id: &BytesN<32>) -> Result<(), Error> {
+    let mission = load_mission(env, mission_id)?;
+    mission.ensure_not_cancelled()?;
+    if mission.status == MissionStatus::Closed {
+        return Err(Error::MissionClosed);
+    }
+    let mut round = load_round(env, round_id)?;
+    if round.finalized || env.ledger().timestamp() > round.end {
+        return Err(Error::RoundEnded);
+    }
+    if round.missions.contains(mission_id) {
+        return Err(Error::AlreadyExists);
+    }
+    if round.missions.len() >= MAX_ROUND_MISSIONS {
+        return Err(Error::RoundFull);
+    }
+
+    round.missions.push_back(mission_id.clone());
+    storage::write(env, &DataKey::Round(round_id), &round);
+    save_entry(
+        env,
+        round_id,
+        mission_id,
+        &RoundEntry {
+            donors: 0,
+            total: 0,
+            sqrt_sum: 0,
+            matched: 0,
+            paid: 0,
+        },
+    );
+    // Finalized rounds no longer need donations recorded against them.
+    let mut rounds = Vec::new(env);
+    for earlier in mission_rounds(env, mission_id).iter() {
+        if load_round(env, earlier).is_ok_and(|earlier| !earlier.finalized) {
+            rounds.push_back(earlier);
+        }
+    }
+    rounds.push_back(round_id);
+    storage::write(env, &DataKey::MissionRounds(mission_id.clone()), &rounds);
+    events::round_joined(env, round_id, mission_id);
+    Ok(())
+}
+
+/// How much of a funded mission's match its owner has earned: all of it,
+/// or for missions in milestone escrow the share of the funds released so
+/// far.
+fn earned(env: &Env, mission_id: &BytesN<32>, mission: &Mission, matched: i128) -> Result<i128, Error> {
+    if !storage::has(env, &DataKey::Milestones(mission_id.clone())) {
+        return Ok(matched);
+    }
+    let released = milestone::released(env, mission_id);
+    if released >= mission.funded_amount {
+        return Ok(matched);
+    }
+    math::mul_div(matched, released, mission.funded_amount)
+}
+
+#[contractimpl]
+impl MissionFundContract {
+    /// Opens a matching round: `sponsor` deposits `pool` of the platform
+    /// token, to be shared by the missions that join before `end` according
+    /// to the donations they receive between `start` and `end`. Returns the
+    /// round id.
+    pub fn create_round(
+        env: Env,
+        sponsor: Address,
+        pool: i128,
+        start: u64,
+        end: u64,
+    ) -> Result<u32, Error> {
+        sponsor.require_auth();
+        if pool <= 0 {
+            return Err(Error::InvalidAmount);
+        }
+        if start >= end || end <= env.ledger().timestamp() {
+            return Err(Error::InvalidDeadline);
+        }
+        let token = asset::platform_token(&env)?;
+        token::Client::new(&env, &token).transfer(&sponsor, &env.current_contract_address(), &pool);
+
+        let round_id: u32 = env.storage().instance().get(&DataKey::RoundCount).unwrap_or(0);
+        env.storage().instance().set(&DataKey::RoundCount, &(round_id + 1));
+        let round = MatchingRound {
+            sponsor,
+            pool,
+            start,
+            end,
+            missions: Vec::new(&env),
+            finalized: false,
+        };
+        storage::write(&env, &DataKey::Round(round_id), &round);
+        events::round_created(&env, round_id, &round.sponsor, pool, start, end);
+        Ok(round_id)
+    }
+
+    pub fn get_round(env: Env, round_id: u32) -> Result<MatchingRound, Error> {
+        load_round(&env, round_id)
+    }
+
+    /// A mission's standing in a round; `RoundNotFound` if it never joined.
+    pub fn get_round_entry(
+        env: Env,
+        round_id: u32,
+        mission_id: BytesN<32>,
+    ) -> Result<RoundEntry, Error> {
+        load_entry(&env, round_id, &mission_id)
+    }
+
+    /// Owner enters the mission into a round, any time before it ends. Only
+    /// donations made after joining count. Team-owned missions join through
+    /// a `TeamAction::JoinRound` proposal.
+    pub fn join_round(env: Env, round_id: u32, mission_id: BytesN<32>) -> Result<(), Error> {
+        upgrade::ensure_current(&env)?;
+        let mission = load_mission(&env, &mission_id)?;
+        mission.owner.require_auth();
+        team::ensure_solo(&env, &mission_id)?;
+        join(&env, round_id, &mission_id)
+    }
+
+    /// Splits the pool once the round is over. Each mission's weight is
+    /// `sqrt_sum² - total`, the part of its quadratic-funding amount not
+    /// already covered by donors; the pool is shared in proportion to the
+    /// weights, and whatever is left (rounding, or no mission with two
+    /// donors) goes back to the sponsor. Missions already expired or
+    /// cancelled get nothing. Anyone may trigger it. Returns the amount
+    /// matched.
+    pub fn finalize_round(env: Env, round_id: u32) -> Result<i128, Error> {
+        upgrade::ensure_current(&env)?;
+        let mut round = load_round(&env, round_id)?;
+        if round.finalized {
+            return Err(Error::RoundEnded);
+        }
+        if env.ledger().timestamp() <= round.end {
+            return Err(Error::RoundOpen);
+        }
+
+        let mut weights = Vec::new(&env);
+        let mut total_weight: i128 = 0;
+        for mission_id in round.missions.iter() {
+            let entry = load_entry(&env, round_id, &mission_id)?;
+            let status = load_mission(&env, &mission_id)?.status(&env);
+            let weight = if matches!(status, MissionStatus::Cancelled | MissionStatus::Expired) {
+                0
+            } else {
+                let squared = entry.sqrt_sum.checked_mul(entry.sqrt_sum).ok_or(Error::Overflow)?;
+                (squared - entry.total).max(0)
+            };
+            weights.push_back(weight);
+            total_weight = math::add(total_weight, weight)?;
+        }
+
+        let mut matched: i128 = 0;
+        if total_weight > 0 {
+            for (mission_id, weight) in round.missions.iter().zip(weights.iter()) {
+                let mut entry = load_entry(&env, round_id, &mission_id)?;
+                entry.matched = math::mul_div(round.pool, weight, total_weight)?;
+                matched += entry.matched;
+                save_entry(&env, round_id, &mission_id, &entry);
+            }
+        }
+        let returned = round.pool - matched;
+        if returned > 0 {
+            let token = asset::platform_token(&env)?;
+            token::Client::new(&env, &token).transfer(
+                &env.current_contract_address(),
+                &round.sponsor,
+                &returned,
+            );
+        }
+        round.finalized = true;
+        storage::write(&env, &DataKey::Round(round_id), &round);
+        events::round_finalized(&env, round_id, matched, returned);
+        Ok(matched)
+    }
+
+    /// Pays out a mission's match to its owner, or a team's payout address,
+    /// once the round is finalized. The match follows the mission's own
+    /// funds: nothing is paid before the target is reached, and missions in
+    /// milestone escrow earn it in step with their releases, so it may take
+    /// several claims. If the mission expires or is cancelled instead, what
+    /// is left goes back to the sponsor. Anyone may trigger it; returns the
+    /// amount paid.
+    pub fn claim_match(env: Env, round_id: u32, mission_id: BytesN<32>) -> Result<i128, Error> {
+        upgrade::ensure_current(&env)?;
+        let round = load_round(&env, round_id)?;
+        if !round.finalized {
+            return Err(Error::RoundOpen);
+        }
+        let mut entry = load_entry(&env, round_id, &mission_id)?;
+        admin::ensure_live(&env, &mission_id)?;
+        let mission = load_mission(&env, &mission_id)?;
+        let (to, due) = match mission.status(&env) {
+            MissionStatus::Cancelled | MissionStatus::Expired => (round.sponsor, entry.matched),
+            MissionStatus::Funded => {
+                let earned = earned(&env, &mission_id, &mission, entry.matched)?;
+                (team::payee(&env, &mission_id, &mission.owner), earned)
+            }
+            _ => return Err(Error::NotFunded),
+        };
+        let amount = due - entry.paid;
+        if amount <= 0 {
+            return Err(Error::NothingToClaim);
+        }
+
+        entry.paid = due;
+        save_entry(&env, round_id, &mission_id, &entry);
+        let token = asset::platform_token(&env)?;
+        token::Client::new(&env, &token).transfer(&env.current_contract_address(), &to, &amount);
+        events::match_claimed(&env, round_id, &mission_id, &to, amount);
+        Ok(amount)
+    }
+}
@@ -0,0 +1,28 @@
+//! Checked arithmetic on token amounts, so an overflow surfaces as
+//! `Error::Overflow` instead of aborting the transaction.
+
+use crate::Error;
+
+pub(crate) fn add(a: i128, b: i128) -> Result<i128, Error> {
+    a.checked_add(b).ok_or(Error::Overflow)
+}
+
+/// Floor of the square root of `n`, which must not be negative.
+pub(crate) fn sqrt(n: i128) -> i128 {
+    if n < 2 {
+        return n;
+    }
+    // Newton's method from above converges to the floor.
+    let mut x = n;
+    let mut y = (x + 1) / 2;
+    while y < x {
+        x = y;
+        y = (x + n / x) / 2;
+    }
+    x
+}
+
+/// `a * b / c`, floored. `c` must not be zero.
+pub(crate) fn mul_div(a: i128, b: i128, c: i128) -> Result<i128, Error> {
+    a.checked_mul(b).map(|product| product / c).ok_or(Error::Overflow)
+}
@@ -0,0 +1,241 @@
+use soroban_sdk::{contractimpl, contracttype, Address, BytesN, Env, String, Vec};
+
+use crate::admin::{self, Role};
+use crate::events;
+use crate::storage::{self, DataKey};
+use crate::{asset, load_mission, math, team, save_mission, upgrade, Error, MissionFundContract, MissionFundContractArgs, MissionFundContractClient};
+
+/// Upper bound on milestones per mission, same as the Solana program.
+pub const MAX_MILESTONES: u32 = 10;
+
+/// A milestone as supplied at registration. `share` is the percentage of the
+/// raised funds paid to the owner when the milestone is approved; the shares
+/// of a mission's milestones must add up to 100.
+#[contracttype]
+#[derive(Clone)]
+pub struct MilestoneSpec {
+    pub title: String,
+    pub share: u32,
+}
+
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MilestoneStatus {
+    Pending,
+    UnderReview,
+    Completed,
+    Rejected,
+}
+
+/// `evidence` holds every hash the owner submitted, latest last, so a
+/// rejected attempt stays on record after resubmission.
+#[contracttype]
+#[derive(Clone)]
+pub struct Milestone {
+    pub title: String,
+    pub share: u32,
+    pub status: MilestoneStatus,
+    pub evidence: Vec<BytesN<32>>,
+    pub submitted_at: Option<u64>,
+    pub approved_at: Option<u64>,
+    pub released: i128,
+}
+
+/// Escrow terms for a mission: who reviews evidence, the milestone list and
+/// how much has been paid out to the owner so far.
+#[contracttype]
+#[derive(Clone)]
+pub struct MilestonePlan {
+    pub reviewer: Address,
+    pub milestones: Vec<Milestone>,
+    pub released: i128,
+}
+
+/// Validates `specs` and stores the escrow plan for a new mission. Missions
+/// registered without milestones get no plan; without a reviewer of their
+/// own, the platform `Reviewer` role is used.
+pub(crate) fn create_plan(
+    env: &Env,
+    mission_id: &BytesN<32>,
+    specs: Vec<MilestoneSpec>,
+    reviewer: Option<Address>,
+) -> Result<(), Error> {
+    if specs.is_empty() {
+        return Ok(());
+    }
+    let reviewer = reviewer
+        .or_else(|| admin::role(env, Role::Reviewer))
+        .ok_or(Error::InvalidMilestones)?;
+    if specs.len() > MAX_MILESTONES {
+        return Err(Error::InvalidMilestones);
+    }
+
+    let mut milestones = Vec::new(env);
+    let mut total_share = 0u32;
+    for spec in specs.iter() {
+        if spec.share == 0 {
+            return Err(Error::InvalidMilestones);
+        }
+        total_share = total_share.saturating_add(spec.share);
+        milestones.push_back(Milestone {
+            title: spec.title,
+            share: spec.share,
+            status: MilestoneStatus::Pending,
+            evidence: Vec::new(env),
+            submitted_at: None,
+            approved_at: None,
+            released: 0,
+        });
+    }
+    if total_share != 100 {
+        return Err(Error::InvalidMilestones);
+    }
+
+    let plan = MilestonePlan {
+        reviewer,
+        milestones,
+        released: 0,
+    };
+    save_plan(env, mission_id, &plan);
+    Ok(())
+}
+
+/// Total paid out to the owner through approved milestones.
+pub(crate) fn released(env: &Env, mission_id: &BytesN<32>) -> i128 {
+    load_plan(env, mission_id).map_or(0, |plan| plan.released)
+}
+
+/// Whether the mission is in milestone escrow and every milestone has been
+/// approved, so nothing could release further funds.
+pub(crate) fn all_completed(env: &Env, mission_id: &BytesN<32>) -> bool {
+    load_plan(env, mission_id).is_ok_and(|plan| {
+        plan.milestones
+            .iter()
+            .all(|milestone| milestone.status == MilestoneStatus::Completed)
+    })
+}
+
+fn load_plan(env: &Env, mission_id: &BytesN<32>) -> Result<MilestonePlan, Error> {
+    storage::read(env, &DataKey::Milestones(mission_id.clone())).ok_or(Error::MilestoneNotFound)
+}
+
+fn save_plan(env: &Env, mission_id: &BytesN<32>, plan: &MilestonePlan) {
+    storage::write(env, &DataKey::Milestones(mission_id.clone()), plan);
+}
+
+/// Puts a milestone under review with `evidence_hash`; see
+/// `submit_milestone`.
+pub(crate) fn submit(
+    env: &Env,
+    mission_id: &BytesN<32>,
+    index: u32,
+    evidence_hash: &BytesN<32>,
+) -> Result<(), Error> {
+    let mission = load_mission(env, mission_id)?;
+    mission.ensure_not_cancelled()?;
+    if !mission.target_reached() {
+        return Err(Error::NotFunded);
+    }
+
+    let mut plan = load_plan(env, mission_id)?;
+    let mut milestone = plan.milestones.get(index).ok_or(Error::MilestoneNotFound)?;
+    if !matches!(
+        milestone.status,
+        MilestoneStatus::Pending | MilestoneStatus::Rejected
+    ) {
+        return Err(Error::InvalidMilestoneStatus);
+    }
+
+    milestone.status = MilestoneStatus::UnderReview;
+    milestone.evidence.push_back(evidence_hash.clone());
+    milestone.submitted_at = Some(env.ledger().timestamp());
+    plan.milestones.set(index, milestone);
+    save_plan(env, mission_id, &plan);
+    events::milestone_submitted(env, mission_id, index, evidence_hash);
+    Ok(())
+}
+
+#[contractimpl]
+impl MissionFundContract {
+    pub fn get_milestone_plan(env: Env, mission_id: BytesN<32>) -> Result<MilestonePlan, Error> {
+        load_plan(&env, &mission_id)
+    }
+
+    /// Owner submits the hash of the evidence for a milestone. Only allowed
+    /// once the mission reached its target; a rejected milestone may be
+    /// resubmitted with new evidence. Team-owned missions submit through a
+    /// `TeamAction::SubmitMilestone` proposal instead.
+    pub fn submit_milestone(
+        env: Env,
+        mission_id: BytesN<32>,
+        index: u32,
+        evidence_hash: BytesN<32>,
+    ) -> Result<(), Error> {
+        upgrade::ensure_current(&env)?;
+        let mission = load_mission(&env, &mission_id)?;
+        mission.owner.require_auth();
+        team::ensure_solo(&env, &mission_id)?;
+        submit(&env, &mission_id, index, &evidence_hash)
+    }
+
+    /// Reviewer verdict on submitted evidence. Approval pays
+    /// `share`% of the funds raised to the mission owner (or a team's payout
+    /// address), taken evenly from every asset held; the last milestone to
+    /// complete also sweeps any rounding remainder. Returns the reference
+    /// value released.
+    pub fn review_milestone(
+        env: Env,
+        mission_id: BytesN<32>,
+        index: u32,
+        approved: bool,
+    ) -> Result<i128, Error> {
+        upgrade::ensure_current(&env)?;
+        let mut mission = load_mission(&env, &mission_id)?;
+        let mut plan = load_plan(&env, &mission_id)?;
+        plan.reviewer.require_auth();
+        mission.ensure_not_cancelled()?;
+        if approved {
+            admin::ensure_live(&env, &mission_id)?;
+        }
+
+        let mut milestone = plan.milestones.get(index).ok_or(Error::MilestoneNotFound)?;
+        if milestone.status != MilestoneStatus::UnderReview {
+            return Err(Error::InvalidMilestoneStatus);
+        }
+
+        if !approved {
+            milestone.status = MilestoneStatus::Rejected;
+            plan.milestones.set(index, milestone);
+            save_plan(&env, &mission_id, &plan);
+            events::milestone_reviewed(&env, &mission_id, index, false, 0);
+            return Ok(0);
+        }
+
+        let unreleased = mission.funded_amount - plan.released;
+        let last = plan
+            .milestones
+            .iter()
+            .enumerate()
+            .all(|(i, m)| i as u32 == index || m.status == MilestoneStatus::Completed);
+        let amount = if last {
+            unreleased
+        } else {
+            math::mul_div(mission.funded_amount, milestone.share as i128, 100)?.min(unreleased)
+        };
+
+        milestone.status = MilestoneStatus::Completed;
+        milestone.approved_at = Some(env.ledger().timestamp());
+        milestone.released = amount;
+        plan.milestones.set(index, milestone);
+        plan.released += amount;
+        save_plan(&env, &mission_id, &plan);
+        events::milestone_reviewed(&env, &mission_id, index, true, amount);
+
+        if amount > 0 {
+            let payee = team::payee(&env, &mission_id, &mission.owner);
+            asset::pay_out(&env, &mut mission, amount, unreleased, &payee)?;
+            save_mission(&env, &mission_id, &mission);
+        }
+        Ok(amount)
+    }
+}
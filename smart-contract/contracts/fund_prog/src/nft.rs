@@ -0,0 +1,341 @@
+use soroban_sdk::{contractimpl, contracttype, vec, Address, BytesN, Env, IntoVal, Map, String, Symbol, TryFromVal, Val, Vec};
+
+use crate::admin;
+use crate::events;
+use crate::hex;
+use crate::index::MAX_PAGE_SIZE;
+use crate::rarity::{self, Rarity};
+use crate::storage::{self, DataKey};
+use crate::upgrade;
+use crate::{Error, Mission, MissionFundContract, MissionFundContractArgs, MissionFundContractClient};
+
+/// A contribution collectible. Every donation mints a fresh token id, so
+/// repeated donations to the same mission never overwrite each other.
+/// `donor` made the donation, whoever holds the token now (for tokens
+/// minted before schema version 5, whoever held it at the migration), and
+/// `position` is the token's place in its owner's `tokens_of` list.
+#[contracttype]
+#[derive(Clone)]
+pub struct Nft {
+    pub owner: Address,
+    pub approved: Option<Address>,
+    pub mission_id: BytesN<32>,
+    pub donor: Address,
+    pub metadata: Map<Symbol, Val>,
+    pub position: u32,
+}
+
+/// Base of every `token_uri` until the admin sets another.
+pub const DEFAULT_BASE_URI: &str = "ipfs://astrofi-nft";
+
+/// Longest base URI `set_base_uri` accepts, so `token_uri` can be assembled
+/// in a fixed buffer.
+pub const MAX_BASE_URI_LEN: u32 = 128;
+
+fn base_uri(env: &Env) -> String {
+    env.storage()
+        .instance()
+        .get(&DataKey::BaseUri)
+        .unwrap_or(String::from_str(env, DEFAULT_BASE_URI))
+}
+
+fn append(out: &mut [u8], bytes: &[u8]) -> usize {
+    out[..bytes.len()].copy_from_slice(bytes);
+    bytes.len()
+}
+
+fn load(env: &Env, token_id: u64) -> Result<Nft, Error> {
+    storage::read(env, &DataKey::Nft(token_id)).ok_or(Error::NftNotFound)
+}
+
+fn save(env: &Env, token_id: u64, nft: &Nft) {
+    storage::write(env, &DataKey::Nft(token_id), nft);
+}
+
+fn balance(env: &Env, owner: &Address) -> u32 {
+    storage::read(env, &DataKey::OwnedNftCount(owner.clone())).unwrap_or(0)
+}
+
+/// Appends `token_id` to `owner`'s tokens and returns its position.
+fn add_owned(env: &Env, owner: &Address, token_id: u64) -> u32 {
+    let position = balance(env, owner);
+    storage::write(env, &DataKey::OwnedNftAt(owner.clone(), position), &token_id);
+    storage::write(env, &DataKey::OwnedNftCount(owner.clone()), &(position + 1));
+    position
+}
+
+/// Takes the token at `position` out of `owner`'s tokens. Their last token
+/// moves into the gap, so the list stays dense.
+fn remove_owned(env: &Env, owner: &Address, position: u32) {
+    let last = balance(env, owner) - 1;
+    let last_key = DataKey::OwnedNftAt(owner.clone(), last);
+    if position != last {
+        if let Some(moved) = storage::read::<u64>(env, &last_key) {
+            storage::write(env, &DataKey::OwnedNftAt(owner.clone(), position), &moved);
+            if let Ok(mut moved_nft) = load(env, moved) {
+                moved_nft.position = position;
+                save(env, moved, &moved_nft);
+            }
+        }
+    }
+    env.storage().persistent().remove(&last_key);
+    storage::write(env, &DataKey::OwnedNftCount(owner.clone()), &last);
+}
+
+fn move_token(env: &Env, token_id: u64, mut nft: Nft, to: Address) {
+    remove_owned(env, &nft.owner, nft.position);
+    nft.position = add_owned(env, &to, token_id);
+
+    events::nft_transferred(env, token_id, &nft.owner, &to);
+    nft.owner = to;
+    nft.approved = None;
+    save(env, token_id, &nft);
+}
+
+/// Builds the metadata of a contribution token. It follows the common
+/// wallet/marketplace JSON schema, so it can be served as-is at `token_uri`:
+///
+/// - `name`: the mission title
+/// - `description`: the mission description
+/// - `image`: the mission image URL
+/// - `attributes`: `{trait_type, value}` pairs for `Mission` (hex mission id),
+///   `Contribution` (amount in the donated asset's base units), `Asset`
+///   (the donated asset's contract address) and `Rarity` (the donor's tier
+///   on the mission, kept current as they top up)
+pub(crate) fn metadata(
+    env: &Env,
+    mission_id: &BytesN<32>,
+    mission: &Mission,
+    asset: &Address,
+    amount: i128,
+    rarity: Rarity,
+) -> Map<Symbol, Val> {
+    let mut mission_hex = [0u8; 64];
+    hex::encode(&mission_id.to_array(), &mut mission_hex);
+    let attributes = vec![
+        env,
+        attribute(env, "Mission", String::from_bytes(env, &mission_hex).into_val(env)),
+        attribute(env, "Contribution", amount.into_val(env)),
+        attribute(env, "Asset", asset.to_string().into_val(env)),
+        rarity_attribute(env, rarity),
+    ];
+
+    let mut metadata: Map<Symbol, Val> = Map::new(env);
+    metadata.set(Symbol::new(env, "name"), mission.title.into_val(env));
+    metadata.set(Symbol::new(env, "description"), mission.desc.into_val(env));
+    metadata.set(Symbol::new(env, "image"), mission.image_url.into_val(env));
+    metadata.set(Symbol::new(env, "attributes"), attributes.into_val(env));
+    metadata
+}
+
+fn attribute(env: &Env, trait_type: &str, value: Val) -> Map<Symbol, Val> {
+    let mut attribute: Map<Symbol, Val> = Map::new(env);
+    attribute.set(
+        Symbol::new(env, "trait_type"),
+        String::from_str(env, trait_type).into_val(env),
+    );
+    attribute.set(Symbol::new(env, "value"), value);
+    attribute
+}
+
+fn rarity_attribute(env: &Env, rarity: Rarity) -> Map<Symbol, Val> {
+    attribute(env, "Rarity", String::from_str(env, rarity.name()).into_val(env))
+}
+
+/// Mints a new collectible for `donor` recording this contribution and
+/// returns its token id.
+pub(crate) fn mint_nft(
+    env: &Env,
+    donor: &Address,
+    mission_id: &BytesN<32>,
+    mission: &Mission,
+    asset: &Address,
+    amount: i128,
+    rarity: Rarity,
+) -> u64 {
+    let metadata = metadata(env, mission_id, mission, asset, amount, rarity);
+    issue(env, donor, mission_id, metadata)
+}
+
+/// Sets the `Rarity` attribute of a token's metadata, adding it to tokens
+/// minted before tiers existed.
+fn with_rarity(env: &Env, mut metadata: Map<Symbol, Val>, rarity: Rarity) -> Map<Symbol, Val> {
+    let key = Symbol::new(env, "attributes");
+    let mut attributes: Vec<Map<Symbol, Val>> = metadata
+        .get(key.clone())
+        .map(|v| v.into_val(env))
+        .unwrap_or(Vec::new(env));
+    let trait_type = Symbol::new(env, "trait_type");
+    let rarity_trait = Some(String::from_str(env, "Rarity"));
+    let position = attributes.iter().position(|attribute| {
+        attribute
+            .get(trait_type.clone())
+            .and_then(|name| String::try_from_val(env, &name).ok())
+            == rarity_trait
+    });
+    match position {
+        Some(i) => attributes.set(i as u32, rarity_attribute(env, rarity)),
+        None => attributes.push_back(rarity_attribute(env, rarity)),
+    }
+    metadata.set(key, attributes.into_val(env));
+    metadata
+}
+
+/// Allocates the next token id and records `owner`, who made the donation,
+/// as its holder.
+fn issue(
+    env: &Env,
+    owner: &Address,
+    mission_id: &BytesN<32>,
+    metadata: Map<Symbol, Val>,
+) -> u64 {
+    let token_id: u64 = env.storage().instance().get(&DataKey::NftCount).unwrap_or(0);
+    env.storage().instance().set(&DataKey::NftCount, &(token_id + 1));
+
+    let nft = Nft {
+        owner: owner.clone(),
+        approved: None,
+        mission_id: mission_id.clone(),
+        donor: owner.clone(),
+        metadata,
+        position: add_owned(env, owner, token_id),
+    };
+    save(env, token_id, &nft);
+
+    events::nft_minted(env, token_id, owner, mission_id);
+    token_id
+}
+
+#[contractimpl]
+impl MissionFundContract {
+    /// Number of contribution NFTs minted so far; ids run from 0 to
+    /// `total_supply() - 1`.
+    pub fn total_supply(env: Env) -> u64 {
+        env.storage().instance().get(&DataKey::NftCount).unwrap_or(0)
+    }
+
+    pub fn owner_of(env: Env, token_id: u64) -> Result<Address, Error> {
+        Ok(load(&env, token_id)?.owner)
+    }
+
+    pub fn balance_of(env: Env, owner: Address) -> u32 {
+        balance(&env, &owner)
+    }
+
+    /// `owner`'s tokens from position `start`. Positions are not stable:
+    /// when a token leaves, the owner's last token takes its place.
+    pub fn tokens_of(env: Env, owner: Address, start: u32, limit: u32) -> Vec<u64> {
+        let end = balance(&env, &owner).min(start.saturating_add(limit.min(MAX_PAGE_SIZE)));
+        let mut tokens = Vec::new(&env);
+        for position in start..end {
+            if let Some(token_id) = storage::read(&env, &DataKey::OwnedNftAt(owner.clone(), position)) {
+                tokens.push_back(token_id);
+            }
+        }
+        tokens
+    }
+
+    /// Where the JSON form of `token_metadata` is published:
+    /// `<base_uri>/mission/<mission id hex>/<token id hex>.json`, with the
+    /// token id as 16 zero-padded hex digits.
+    pub fn token_uri(env: Env, token_id: u64) -> Result<String, Error> {
+        let nft = load(&env, token_id)?;
+        let base = base_uri(&env);
+        let mut uri = [0u8; MAX_BASE_URI_LEN as usize + 96];
+        let mut len = base.len() as usize;
+        base.copy_into_slice(&mut uri[..len]);
+        len += append(&mut uri[len..], b"/mission/");
+        len += hex::encode(&nft.mission_id.to_array(), &mut uri[len..]);
+        len += append(&mut uri[len..], b"/");
+        len += hex::encode(&token_id.to_be_bytes(), &mut uri[len..]);
+        len += append(&mut uri[len..], b".json");
+        Ok(String::from_bytes(&env, &uri[..len]))
+    }
+
+    pub fn get_base_uri(env: Env) -> String {
+        base_uri(&env)
+    }
+
+    /// Admin sets the prefix of every `token_uri`, without a trailing slash.
+    pub fn set_base_uri(env: Env, base_uri: String) -> Result<(), Error> {
+        admin::require_admin(&env)?;
+        if base_uri.len() > MAX_BASE_URI_LEN {
+            return Err(Error::InvalidConfig);
+        }
+        env.storage().instance().set(&DataKey::BaseUri, &base_uri);
+        events::base_uri_set(&env, &base_uri);
+        Ok(())
+    }
+
+    /// The token's metadata, with `Rarity` at the tier its donor holds on
+    /// the mission now.
+    pub fn token_metadata(env: Env, token_id: u64) -> Result<Map<Symbol, Val>, Error> {
+        let nft = load(&env, token_id)?;
+        Ok(match rarity::donor_tier(&env, &nft.mission_id, &nft.donor) {
+            Some(rarity) => with_rarity(&env, nft.metadata, rarity),
+            None => nft.metadata,
+        })
+    }
+
+    /// Same as `token_metadata`. Before tokens had ids of their own this
+    /// took a mission id; callers still passing one must switch to the
+    /// token id.
+    pub fn get_nft_metadata(env: Env, token_id: u64) -> Result<Map<Symbol, Val>, Error> {
+        Self::token_metadata(env, token_id)
+    }
+
+    pub fn get_approved(env: Env, token_id: u64) -> Result<Option<Address>, Error> {
+        Ok(load(&env, token_id)?.approved)
+    }
+
+    /// Lets `operator` move `token_id` once via `transfer_from`. Passing
+    /// `None` clears an existing approval.
+    pub fn approve(
+        env: Env,
+        owner: Address,
+        operator: Option<Address>,
+        token_id: u64,
+    ) -> Result<(), Error> {
+        owner.require_auth();
+        upgrade::ensure_current(&env)?;
+        let mut nft = load(&env, token_id)?;
+        if nft.owner != owner {
+            return Err(Error::NotOwner);
+        }
+        events::nft_approved(&env, token_id, &owner, &operator);
+        nft.approved = operator;
+        save(&env, token_id, &nft);
+        Ok(())
+    }
+
+    pub fn transfer(env: Env, from: Address, to: Address, token_id: u64) -> Result<(), Error> {
+        from.require_auth();
+        upgrade::ensure_current(&env)?;
+        let nft = load(&env, token_id)?;
+        if nft.owner != from {
+            return Err(Error::NotOwner);
+        }
+        move_token(&env, token_id, nft, to);
+        Ok(())
+    }
+
+    pub fn transfer_from(
+        env: Env,
+        spender: Address,
+        from: Address,
+        to: Address,
+        token_id: u64,
+    ) -> Result<(), Error> {
+        spender.require_auth();
+        upgrade::ensure_current(&env)?;
+        let nft = load(&env, token_id)?;
+        if nft.owner != from {
+            return Err(Error::NotOwner);
+        }
+        if nft.approved != Some(spender) {
+            return Err(Error::NotApproved);
+        }
+        move_token(&env, token_id, nft, to);
+        Ok(())
+    }
+}
@@ -0,0 +1,148 @@
+use soroban_sdk::{contractimpl, contracttype, Address, BytesN, Env, Vec};
+
+use crate::storage::{self, DataKey};
+use crate::{accept_donation, attestor, events, open_for_funding, Error, Payment, MissionFundContract, MissionFundContractArgs, MissionFundContractClient};
+
+/// Upper bound on live pledges per donor, so their pledge list stays a
+/// bounded entry.
+pub const MAX_DONOR_PLEDGES: u32 = 20;
+
+/// A recurring donation: `amount` of `asset` every `period` seconds until
+/// `cap` has been given in total. Installments are pulled by
+/// `collect_pledge` through the allowance the donor grants this contract on
+/// `asset`, which must cover what is left of the cap.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Pledge {
+    pub asset: Address,
+    pub amount: i128,
+    pub period: u64,
+    pub cap: i128,
+    pub collected: i128,
+    pub next_due: u64,
+}
+
+fn pledge_key(mission_id: &BytesN<32>, donor: &Address) -> DataKey {
+    DataKey::Pledge(mission_id.clone(), donor.clone())
+}
+
+fn load(env: &Env, mission_id: &BytesN<32>, donor: &Address) -> Result<Pledge, Error> {
+    storage::read(env, &pledge_key(mission_id, donor)).ok_or(Error::PledgeNotFound)
+}
+
+fn donor_pledges(env: &Env, donor: &Address) -> Vec<BytesN<32>> {
+    storage::read(env, &DataKey::DonorPledges(donor.clone())).unwrap_or(Vec::new(env))
+}
+
+fn remove(env: &Env, mission_id: &BytesN<32>, donor: &Address) {
+    env.storage().persistent().remove(&pledge_key(mission_id, donor));
+    let mut pledges = donor_pledges(env, donor);
+    if let Some(position) = pledges.first_index_of(mission_id) {
+        pledges.remove(position);
+    }
+    storage::write(env, &DataKey::DonorPledges(donor.clone()), &pledges);
+}
+
+#[contractimpl]
+impl MissionFundContract {
+    /// Donor commits to giving `amount` of `asset` to the mission every
+    /// `period` seconds, up to `cap` in total. The first installment is due
+    /// straight away. Before it is collected the donor must `approve` this
+    /// contract on `asset` for the cap, with an expiry past the last
+    /// installment.
+    pub fn create_pledge(
+        env: Env,
+        mission_id: BytesN<32>,
+        donor: Address,
+        asset: Address,
+        amount: i128,
+        period: u64,
+        cap: i128,
+    ) -> Result<Pledge, Error> {
+        donor.require_auth();
+        if amount <= 0 || cap < amount {
+            return Err(Error::InvalidAmount);
+        }
+        if period == 0 {
+            return Err(Error::InvalidConfig);
+        }
+        let mission = open_for_funding(&env, &mission_id)?;
+        attestor::ensure_verified(&env, &mission_id, &donor)?;
+        if !mission.funds.contains_key(asset.clone()) {
+            return Err(Error::UnsupportedAsset);
+        }
+        let key = pledge_key(&mission_id, &donor);
+        if storage::has(&env, &key) {
+            return Err(Error::AlreadyExists);
+        }
+        let mut pledges = donor_pledges(&env, &donor);
+        if pledges.len() >= MAX_DONOR_PLEDGES {
+            return Err(Error::TooManyPledges);
+        }
+
+        let pledge = Pledge {
+            asset,
+            amount,
+            period,
+            cap,
+            collected: 0,
+            next_due: env.ledger().timestamp(),
+        };
+        storage::write(&env, &key, &pledge);
+        pledges.push_back(mission_id.clone());
+        storage::write(&env, &DataKey::DonorPledges(donor.clone()), &pledges);
+        events::pledged(&env, &mission_id, &donor, &pledge);
+        Ok(pledge)
+    }
+
+    /// Takes the installment that is due, which is the pledged amount or
+    /// whatever is left of the cap, and mints the donor a contribution NFT
+    /// as for any donation; its token id is returned. Each period can be
+    /// collected once, so periods missed can be caught up on. The pledge
+    /// ends once the cap is reached. Anyone may trigger it; the donor is
+    /// only ever charged what they pledged.
+    pub fn collect_pledge(env: Env, mission_id: BytesN<32>, donor: Address) -> Result<u64, Error> {
+        let mut pledge = load(&env, &mission_id, &donor)?;
+        if env.ledger().timestamp() < pledge.next_due {
+            return Err(Error::PledgeNotDue);
+        }
+        let installment = pledge.amount.min(pledge.cap - pledge.collected);
+        let token_id = accept_donation(
+            &env,
+            &mission_id,
+            &donor,
+            &pledge.asset,
+            installment,
+            Payment::Allowance,
+        )?;
+
+        pledge.collected += installment;
+        pledge.next_due = pledge.next_due.saturating_add(pledge.period);
+        if pledge.collected >= pledge.cap {
+            remove(&env, &mission_id, &donor);
+        } else {
+            storage::write(&env, &pledge_key(&mission_id, &donor), &pledge);
+        }
+        events::pledge_collected(&env, &mission_id, &donor, installment, pledge.collected);
+        Ok(token_id)
+    }
+
+    /// Donor stops the pledge. Installments already collected stay with the
+    /// mission.
+    pub fn cancel_pledge(env: Env, mission_id: BytesN<32>, donor: Address) -> Result<(), Error> {
+        donor.require_auth();
+        load(&env, &mission_id, &donor)?;
+        remove(&env, &mission_id, &donor);
+        events::pledge_cancelled(&env, &mission_id, &donor);
+        Ok(())
+    }
+
+    pub fn get_pledge(env: Env, mission_id: BytesN<32>, donor: Address) -> Result<Pledge, Error> {
+        load(&env, &mission_id, &donor)
+    }
+
+    /// Missions `donor` has a live pledge to, oldest first.
+    pub fn get_donor_pledges(env: Env, donor: Address) -> Vec<BytesN<32>> {
+        donor_pledges(&env, &donor)
+    }
+}
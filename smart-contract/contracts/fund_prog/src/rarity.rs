@@ -0,0 +1,131 @@
+use soroban_sdk::{contractimpl, contracttype, Address, BytesN, Env};
+
+use crate::storage::DataKey;
+use crate::contribution::{self, Contribution};
+use crate::{admin, events, load_mission, math, Error, MissionFundContract, MissionFundContractArgs, MissionFundContractClient};
+
+/// Tier of a contribution NFT, from the donor's share of the mission target.
+/// Same tiers as `calculate_rarity` in the Solana program.
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, PartialOrd, Ord)]
+pub enum Rarity {
+    Common,
+    Rare,
+    Epic,
+    Legendary,
+}
+
+impl Rarity {
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Rarity::Common => "Common",
+            Rarity::Rare => "Rare",
+            Rarity::Epic => "Epic",
+            Rarity::Legendary => "Legendary",
+        }
+    }
+}
+
+/// Smallest whole percentage of the target a donor's total contribution
+/// must reach for each tier; anything below `rare` is `Common`.
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct RarityThresholds {
+    pub rare: u32,
+    pub epic: u32,
+    pub legendary: u32,
+}
+
+impl RarityThresholds {
+    /// The Solana program's cut-offs: up to 1% Common, 2–5% Rare, 6–15%
+    /// Epic, above that Legendary.
+    pub(crate) const DEFAULT: RarityThresholds = RarityThresholds {
+        rare: 2,
+        epic: 6,
+        legendary: 16,
+    };
+}
+
+fn thresholds(env: &Env) -> RarityThresholds {
+    env.storage()
+        .instance()
+        .get(&DataKey::RarityThresholds)
+        .unwrap_or(RarityThresholds::DEFAULT)
+}
+
+/// Tier of a total contribution of `amount` towards `target`, both in the
+/// mission's reference asset. The percentage is floored, as on Solana;
+/// amounts too large to scale by 100 are divided by a hundredth of the
+/// target instead. Without a target (as legacy missions may have) there is
+/// no share to rank, so every contribution is `Common`.
+pub(crate) fn tier(env: &Env, amount: i128, target: i128) -> Rarity {
+    if target <= 0 {
+        return Rarity::Common;
+    }
+    let percent = math::mul_div(amount, 100, target)
+        .unwrap_or_else(|_| amount / (target / 100).max(1));
+    let thresholds = thresholds(env);
+    if percent >= thresholds.legendary as i128 {
+        Rarity::Legendary
+    } else if percent >= thresholds.epic as i128 {
+        Rarity::Epic
+    } else if percent >= thresholds.rare as i128 {
+        Rarity::Rare
+    } else {
+        Rarity::Common
+    }
+}
+
+/// The tier `donor` holds on the mission, if they have donated to it. It
+/// is derived from their contribution whenever a token is read, so a
+/// top-up into a new tier re-tiers their earlier tokens without writing
+/// anything.
+pub(crate) fn donor_tier(env: &Env, mission_id: &BytesN<32>, donor: &Address) -> Option<Rarity> {
+    let contribution = contribution::load(env, mission_id, donor)?;
+    let target = load_mission(env, mission_id).ok()?.target_amount;
+    Some(tier(env, contribution.amount, target))
+}
+
+/// Announces that a donation of `value` moved `donor` into `rarity` from
+/// the tier of their earlier contributions, if it did.
+pub(crate) fn track(
+    env: &Env,
+    mission_id: &BytesN<32>,
+    donor: &Address,
+    contribution: &Contribution,
+    value: i128,
+    target: i128,
+) {
+    if contribution.count < 2 {
+        return;
+    }
+    let rarity = tier(env, contribution.amount, target);
+    if tier(env, contribution.amount - value, target) != rarity {
+        events::donor_retiered(env, mission_id, donor, rarity);
+    }
+}
+
+#[contractimpl]
+impl MissionFundContract {
+    pub fn get_rarity_thresholds(env: Env) -> RarityThresholds {
+        thresholds(&env)
+    }
+
+    /// Admin override of the tier cut-offs, which must be strictly
+    /// increasing and start above zero. Tiers are derived when a token is
+    /// read, so this re-tiers existing tokens as well.
+    pub fn set_rarity_thresholds(env: Env, thresholds: RarityThresholds) -> Result<(), Error> {
+        admin::require_admin(&env)?;
+        if thresholds.rare == 0
+            || thresholds.rare >= thresholds.epic
+            || thresholds.epic >= thresholds.legendary
+        {
+            return Err(Error::InvalidConfig);
+        }
+        env.storage()
+            .instance()
+            .set(&DataKey::RarityThresholds, &thresholds);
+        events::rarity_thresholds_set(&env, &thresholds);
+        Ok(())
+    }
+}
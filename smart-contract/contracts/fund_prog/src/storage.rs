@@ -0,0 +1,273 @@
+use soroban_sdk::{contracttype, Address, BytesN, Env, IntoVal, Map, String, Symbol, TryFromVal, Val};
+
+use crate::admin::Role;
+use crate::MissionStatus;
+
+pub(crate) const DAY_IN_LEDGERS: u32 = 17_280;
+
+/// Every record the contract persists lives under one of these keys, so
+/// different record kinds can never collide in the same key space.
+///
+/// `Admin`, `PendingAdmin`, `Role`, `Paused`, `Token`, `FeeBps`, `NftCount`,
+/// `TtlConfig`, `MissionCount`, `SchemaVersion`, `MigrationCursor`,
+/// `MigrationItem`, `BaseUri`, `Rates`, `RarityThresholds` and `RoundCount` are instance
+/// entries; the rest are persistent entries.
+#[contracttype]
+#[derive(Clone)]
+pub enum DataKey {
+    Admin,
+    PendingAdmin,
+    Role(Role),
+    Paused,
+    Token,
+    FeeBps,
+    NftCount,
+    TtlConfig,
+    MissionCount,
+    SchemaVersion,
+    MigrationCursor,
+    MigrationItem,
+    BaseUri,
+    Rates,
+    RarityThresholds,
+    RoundCount,
+    Mission(BytesN<32>),
+    MissionAt(u32),
+    OwnerMissionCount(Address),
+    OwnerMissionAt(Address, u32),
+    Milestones(BytesN<32>),
+    MissionPaused(BytesN<32>),
+    MetadataChanges(BytesN<32>),
+    Contribution(BytesN<32>, Address),
+    ContributionAssets(BytesN<32>, Address),
+    DonorCount(BytesN<32>),
+    DonorAt(BytesN<32>, u32),
+    DonorMissionCount(Address),
+    DonorMissionAt(Address, u32),
+    Nft(u64),
+    OwnedNftCount(Address),
+    OwnedNftAt(Address, u32),
+    Round(u32),
+    RoundMission(u32, BytesN<32>),
+    RoundDonation(u32, BytesN<32>, Address),
+    MissionRounds(BytesN<32>),
+    Pledge(BytesN<32>, Address),
+    DonorPledges(Address),
+    Team(BytesN<32>),
+    ProposalCount(BytesN<32>),
+    Proposal(BytesN<32>, u32),
+    Attestor(BytesN<32>),
+    TargetCallback(BytesN<32>),
+    PendingCallback(BytesN<32>),
+}
+
+/// Ledger counts used when extending entry lifetimes: once an entry's TTL
+/// drops below `*_threshold` it is pushed back out to `*_extend_to`.
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct TtlConfig {
+    pub persistent_threshold: u32,
+    pub persistent_extend_to: u32,
+    pub instance_threshold: u32,
+    pub instance_extend_to: u32,
+}
+
+impl TtlConfig {
+    pub(crate) const DEFAULT: TtlConfig = TtlConfig {
+        persistent_threshold: 30 * DAY_IN_LEDGERS,
+        persistent_extend_to: 120 * DAY_IN_LEDGERS,
+        instance_threshold: 7 * DAY_IN_LEDGERS,
+        instance_extend_to: 30 * DAY_IN_LEDGERS,
+    };
+}
+
+pub(crate) fn ttl_config(env: &Env) -> TtlConfig {
+    env.storage()
+        .instance()
+        .get(&DataKey::TtlConfig)
+        .unwrap_or(TtlConfig::DEFAULT)
+}
+
+pub(crate) fn bump_instance(env: &Env) {
+    let config = ttl_config(env);
+    env.storage()
+        .instance()
+        .extend_ttl(config.instance_threshold, config.instance_extend_to);
+}
+
+/// Extends a persistent entry, which must exist.
+pub(crate) fn extend(env: &Env, key: &DataKey) {
+    let config = ttl_config(env);
+    env.storage().persistent().extend_ttl(
+        key,
+        config.persistent_threshold,
+        config.persistent_extend_to,
+    );
+}
+
+/// Persistent read that keeps the entry (and the contract instance) live.
+pub(crate) fn read<V: TryFromVal<Env, Val>>(env: &Env, key: &DataKey) -> Option<V> {
+    let value = env.storage().persistent().get(key);
+    if value.is_some() {
+        extend(env, key);
+    }
+    bump_instance(env);
+    value
+}
+
+/// Persistent write that keeps the entry (and the contract instance) live.
+pub(crate) fn write<V: IntoVal<Env, Val>>(env: &Env, key: &DataKey, value: &V) {
+    env.storage().persistent().set(key, value);
+    extend(env, key);
+    bump_instance(env);
+}
+
+pub(crate) fn has(env: &Env, key: &DataKey) -> bool {
+    env.storage().persistent().has(key)
+}
+
+/// Extends a persistent entry if it exists.
+pub(crate) fn extend_if_present(env: &Env, key: &DataKey) {
+    if has(env, key) {
+        extend(env, key);
+    }
+}
+
+/// `Mission` as written by the original contract, before deadlines and
+/// withdrawals were tracked. Only read by `migrate_legacy_missions`.
+#[contracttype(export = false)]
+#[derive(Clone)]
+pub struct LegacyMission {
+    pub title: String,
+    pub desc: String,
+    pub image_url: String,
+    pub target_amount: i128,
+    pub funded_amount: i128,
+    pub owner: Address,
+}
+
+impl LegacyMission {
+    /// The original contract counted donations without taking any tokens,
+    /// so nothing of its `funded_amount` is in custody: the mission starts
+    /// over from zero, with the `deadline` it was migrated with.
+    pub(crate) fn into_v3(self, deadline: u64) -> MissionV3 {
+        MissionV3 {
+            title: self.title,
+            desc: self.desc,
+            image_url: self.image_url,
+            target_amount: self.target_amount,
+            funded_amount: 0,
+            owner: self.owner,
+            deadline,
+            withdrawn: 0,
+            fees_paid: 0,
+            status: MissionStatus::Active,
+            refund_pool: 0,
+        }
+    }
+}
+
+/// `Mission` as written by schema version 1, before platform fees were
+/// tallied. Only read by `migrate`.
+#[contracttype(export = false)]
+#[derive(Clone)]
+pub struct MissionV1 {
+    pub title: String,
+    pub desc: String,
+    pub image_url: String,
+    pub target_amount: i128,
+    pub funded_amount: i128,
+    pub owner: Address,
+    pub deadline: u64,
+    pub withdrawn: i128,
+}
+
+/// `Mission` as written by schema version 2, before owners could close or
+/// cancel missions. Only read by `migrate`.
+#[contracttype(export = false)]
+#[derive(Clone)]
+pub struct MissionV2 {
+    pub title: String,
+    pub desc: String,
+    pub image_url: String,
+    pub target_amount: i128,
+    pub funded_amount: i128,
+    pub owner: Address,
+    pub deadline: u64,
+    pub withdrawn: i128,
+    pub fees_paid: i128,
+}
+
+impl From<MissionV1> for MissionV2 {
+    fn from(v1: MissionV1) -> Self {
+        MissionV2 {
+            title: v1.title,
+            desc: v1.desc,
+            image_url: v1.image_url,
+            target_amount: v1.target_amount,
+            funded_amount: v1.funded_amount,
+            owner: v1.owner,
+            deadline: v1.deadline,
+            withdrawn: v1.withdrawn,
+            fees_paid: 0,
+        }
+    }
+}
+
+/// `Mission` as written by schema version 3, before missions accepted
+/// several assets. Only read by `migrate`.
+#[contracttype(export = false)]
+#[derive(Clone)]
+pub struct MissionV3 {
+    pub title: String,
+    pub desc: String,
+    pub image_url: String,
+    pub target_amount: i128,
+    pub funded_amount: i128,
+    pub owner: Address,
+    pub deadline: u64,
+    pub withdrawn: i128,
+    pub fees_paid: i128,
+    pub status: MissionStatus,
+    pub refund_pool: i128,
+}
+
+impl From<MissionV2> for MissionV3 {
+    fn from(v2: MissionV2) -> Self {
+        MissionV3 {
+            title: v2.title,
+            desc: v2.desc,
+            image_url: v2.image_url,
+            target_amount: v2.target_amount,
+            funded_amount: v2.funded_amount,
+            owner: v2.owner,
+            deadline: v2.deadline,
+            withdrawn: v2.withdrawn,
+            fees_paid: v2.fees_paid,
+            status: MissionStatus::Active,
+            refund_pool: 0,
+        }
+    }
+}
+
+/// `Nft` as written by schema version 4, before tokens recorded their
+/// donor and their place in the owner's list. Only read by `migrate`.
+#[contracttype(export = false)]
+#[derive(Clone)]
+pub struct NftV4 {
+    pub owner: Address,
+    pub approved: Option<Address>,
+    pub mission_id: BytesN<32>,
+    pub metadata: Map<Symbol, Val>,
+}
+
+/// Keys under which schema version 4 kept whole lists, one entry per
+/// owner or donor. They encode exactly as the `DataKey` variants of the
+/// same names did. Only read by `migrate`.
+#[contracttype(export = false)]
+#[derive(Clone)]
+pub enum ListKeyV4 {
+    OwnerMissions(Address),
+    DonorMissions(Address),
+    OwnedNfts(Address),
+}
@@ -0,0 +1,291 @@
+use soroban_sdk::{contractimpl, contracttype, Address, BytesN, Env, String, Vec};
+
+use crate::callback::{self, TargetCallback};
+use crate::index::MAX_PAGE_SIZE;
+use crate::storage::{self, DataKey};
+use crate::{asset, events, lifecycle, load_mission, matching, milestone, upgrade, withdraw_to, Error, MissionFundContract, MissionFundContractArgs, MissionFundContractClient};
+
+/// Upper bound on signers in a mission team.
+pub const MAX_TEAM_SIGNERS: u32 = 10;
+
+/// The signer set that runs a team-owned mission. Everything the owner
+/// could do alone, changes to the team included, needs `threshold`
+/// distinct signers to approve a proposal. Milestone releases and matches,
+/// which are paid without a proposal, go to `payout`. `epoch` counts
+/// rotations; proposals made under an earlier team can no longer be
+/// approved or executed.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Team {
+    pub signers: Vec<Address>,
+    pub threshold: u32,
+    pub payout: Address,
+    pub epoch: u32,
+}
+
+/// What a team proposal does once executed.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum TeamAction {
+    /// Pays everything withdrawable to the given address, as `withdraw`.
+    Withdraw(Address),
+    /// Submits evidence for a milestone, as `submit_milestone`.
+    SubmitMilestone(u32, BytesN<32>),
+    /// Replaces the signers, threshold and payout address.
+    SetTeam(Vec<Address>, u32, Address),
+    /// Replaces title, description and image, as `update_mission_metadata`.
+    UpdateMetadata(String, String, String),
+    /// Stops new donations, as `close_mission`.
+    Close,
+    /// Calls the mission off, as `cancel_mission`.
+    Cancel,
+    /// Sets the reference and accepted assets, as `set_mission_assets`.
+    SetAssets(Address, Vec<Address>),
+    /// Registers the target callback, as `set_target_callback`.
+    SetTargetCallback(TargetCallback),
+    /// Removes the target callback.
+    ClearTargetCallback,
+    /// Enters a matching round, as `join_round`.
+    JoinRound(u32),
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Proposal {
+    pub action: TeamAction,
+    pub approvals: Vec<Address>,
+    pub epoch: u32,
+    pub executed: bool,
+}
+
+fn load_team(env: &Env, mission_id: &BytesN<32>) -> Result<Team, Error> {
+    storage::read(env, &DataKey::Team(mission_id.clone())).ok_or(Error::NotTeamMember)
+}
+
+fn load_proposal(env: &Env, mission_id: &BytesN<32>, proposal_id: u32) -> Result<Proposal, Error> {
+    storage::read(env, &DataKey::Proposal(mission_id.clone(), proposal_id))
+        .ok_or(Error::ProposalNotFound)
+}
+
+fn save_proposal(env: &Env, mission_id: &BytesN<32>, proposal_id: u32, proposal: &Proposal) {
+    storage::write(env, &DataKey::Proposal(mission_id.clone(), proposal_id), proposal);
+}
+
+/// Fails for team-owned missions, whose owner alone may not act for them.
+pub(crate) fn ensure_solo(env: &Env, mission_id: &BytesN<32>) -> Result<(), Error> {
+    if storage::has(env, &DataKey::Team(mission_id.clone())) {
+        return Err(Error::TeamApprovalRequired);
+    }
+    Ok(())
+}
+
+/// Who receives what a mission is paid without a proposal: the team's
+/// payout address, or the owner of a solo mission.
+pub(crate) fn payee(env: &Env, mission_id: &BytesN<32>, owner: &Address) -> Address {
+    storage::read::<Team>(env, &DataKey::Team(mission_id.clone()))
+        .map_or(owner.clone(), |team| team.payout)
+}
+
+/// Signers must be distinct and no more than `MAX_TEAM_SIGNERS`, and the
+/// threshold between one and their number.
+fn check_team(signers: &Vec<Address>, threshold: u32) -> Result<(), Error> {
+    if signers.len() > MAX_TEAM_SIGNERS || threshold == 0 || threshold > signers.len() {
+        return Err(Error::InvalidConfig);
+    }
+    for (i, signer) in signers.iter().enumerate() {
+        if signers.first_index_of(&signer) != Some(i as u32) {
+            return Err(Error::InvalidConfig);
+        }
+    }
+    Ok(())
+}
+
+/// Loads the team and checks `signer` is on it and has signed the call.
+fn authorized_signer(env: &Env, mission_id: &BytesN<32>, signer: &Address) -> Result<Team, Error> {
+    signer.require_auth();
+    let team = load_team(env, mission_id)?;
+    if !team.signers.contains(signer) {
+        return Err(Error::NotTeamMember);
+    }
+    Ok(team)
+}
+
+/// An open proposal of the current team.
+fn open_proposal(
+    env: &Env,
+    mission_id: &BytesN<32>,
+    proposal_id: u32,
+    team: &Team,
+) -> Result<Proposal, Error> {
+    let proposal = load_proposal(env, mission_id, proposal_id)?;
+    if proposal.executed || proposal.epoch != team.epoch {
+        return Err(Error::ProposalClosed);
+    }
+    Ok(proposal)
+}
+
+#[contractimpl]
+impl MissionFundContract {
+    /// Owner hands the mission to a team of `signers`, `threshold` of whom
+    /// must approve every `TeamAction` from then on; milestone releases and
+    /// matches go to `payout`. Once set, the team can only be changed by a
+    /// `TeamAction::SetTeam` proposal.
+    pub fn set_team(
+        env: Env,
+        mission_id: BytesN<32>,
+        signers: Vec<Address>,
+        threshold: u32,
+        payout: Address,
+    ) -> Result<(), Error> {
+        upgrade::ensure_current(&env)?;
+        let mission = load_mission(&env, &mission_id)?;
+        mission.owner.require_auth();
+        ensure_solo(&env, &mission_id)?;
+        check_team(&signers, threshold)?;
+        let team = Team {
+            signers,
+            threshold,
+            payout,
+            epoch: 0,
+        };
+        storage::write(&env, &DataKey::Team(mission_id.clone()), &team);
+        events::team_set(&env, &mission_id, &team);
+        Ok(())
+    }
+
+    pub fn get_team(env: Env, mission_id: BytesN<32>) -> Option<Team> {
+        storage::read(&env, &DataKey::Team(mission_id))
+    }
+
+    /// A signer proposes `action`, counting as its first approval. Returns
+    /// the proposal id.
+    pub fn propose(
+        env: Env,
+        mission_id: BytesN<32>,
+        signer: Address,
+        action: TeamAction,
+    ) -> Result<u32, Error> {
+        let team = authorized_signer(&env, &mission_id, &signer)?;
+        if let TeamAction::SetTeam(signers, threshold, _) = &action {
+            check_team(signers, *threshold)?;
+        }
+        let key = DataKey::ProposalCount(mission_id.clone());
+        let proposal_id: u32 = storage::read(&env, &key).unwrap_or(0);
+        storage::write(&env, &key, &(proposal_id + 1));
+        save_proposal(
+            &env,
+            &mission_id,
+            proposal_id,
+            &Proposal {
+                action,
+                approvals: Vec::from_array(&env, [signer.clone()]),
+                epoch: team.epoch,
+                executed: false,
+            },
+        );
+        events::proposed(&env, &mission_id, proposal_id, &signer);
+        Ok(proposal_id)
+    }
+
+    /// Another signer approves a proposal. Returns the approvals so far.
+    pub fn approve_proposal(
+        env: Env,
+        mission_id: BytesN<32>,
+        proposal_id: u32,
+        signer: Address,
+    ) -> Result<u32, Error> {
+        let team = authorized_signer(&env, &mission_id, &signer)?;
+        let mut proposal = open_proposal(&env, &mission_id, proposal_id, &team)?;
+        if proposal.approvals.contains(&signer) {
+            return Err(Error::AlreadyExists);
+        }
+        proposal.approvals.push_back(signer.clone());
+        save_proposal(&env, &mission_id, proposal_id, &proposal);
+        events::proposal_approved(&env, &mission_id, proposal_id, &signer);
+        Ok(proposal.approvals.len())
+    }
+
+    /// Carries out a proposal once it has `threshold` approvals. Anyone may
+    /// trigger it. Returns the reference value paid out, if any.
+    pub fn execute_proposal(env: Env, mission_id: BytesN<32>, proposal_id: u32) -> Result<i128, Error> {
+        upgrade::ensure_current(&env)?;
+        let mut team = load_team(&env, &mission_id)?;
+        let mut proposal = open_proposal(&env, &mission_id, proposal_id, &team)?;
+        if proposal.approvals.len() < team.threshold {
+            return Err(Error::NotApproved);
+        }
+        proposal.executed = true;
+        save_proposal(&env, &mission_id, proposal_id, &proposal);
+        events::proposal_executed(&env, &mission_id, proposal_id);
+
+        match proposal.action {
+            TeamAction::Withdraw(to) => withdraw_to(&env, &mission_id, &to),
+            TeamAction::SubmitMilestone(index, evidence_hash) => {
+                milestone::submit(&env, &mission_id, index, &evidence_hash)?;
+                Ok(0)
+            }
+            TeamAction::SetTeam(signers, threshold, payout) => {
+                team.signers = signers;
+                team.threshold = threshold;
+                team.payout = payout;
+                team.epoch += 1;
+                storage::write(&env, &DataKey::Team(mission_id.clone()), &team);
+                events::team_set(&env, &mission_id, &team);
+                Ok(0)
+            }
+            TeamAction::UpdateMetadata(title, desc, image_url) => {
+                lifecycle::update_metadata(&env, &mission_id, title, desc, image_url)?;
+                Ok(0)
+            }
+            TeamAction::Close => {
+                lifecycle::close(&env, &mission_id)?;
+                Ok(0)
+            }
+            TeamAction::Cancel => {
+                lifecycle::cancel(&env, &mission_id)?;
+                Ok(0)
+            }
+            TeamAction::SetAssets(reference, accepted) => {
+                asset::set_assets(&env, &mission_id, reference, accepted)?;
+                Ok(0)
+            }
+            TeamAction::SetTargetCallback(target_callback) => {
+                callback::set(&env, &mission_id, Some(target_callback));
+                Ok(0)
+            }
+            TeamAction::ClearTargetCallback => {
+                callback::set(&env, &mission_id, None);
+                Ok(0)
+            }
+            TeamAction::JoinRound(round_id) => {
+                matching::join(&env, round_id, &mission_id)?;
+                Ok(0)
+            }
+        }
+    }
+
+    pub fn get_proposal(env: Env, mission_id: BytesN<32>, proposal_id: u32) -> Result<Proposal, Error> {
+        load_proposal(&env, &mission_id, proposal_id)
+    }
+
+    /// Extends the lifetime of a page of the mission's proposals, so an
+    /// open one is not archived while it gathers approvals. Anyone may call
+    /// it, alongside `bump_mission`; returns how many were covered.
+    pub fn bump_proposals(
+        env: Env,
+        mission_id: BytesN<32>,
+        start: u32,
+        limit: u32,
+    ) -> Result<u32, Error> {
+        if !storage::has(&env, &DataKey::Mission(mission_id.clone())) {
+            return Err(Error::MissionNotFound);
+        }
+        let count: u32 =
+            storage::read(&env, &DataKey::ProposalCount(mission_id.clone())).unwrap_or(0);
+        let end = count.min(start.saturating_add(limit.min(MAX_PAGE_SIZE)));
+        for proposal_id in start..end {
+            storage::extend_if_present(&env, &DataKey::Proposal(mission_id.clone(), proposal_id));
+        }
+        Ok(end.saturating_sub(start))
+    }
+}
@@ -0,0 +1,3494 @@
+#![cfg(test)]
+
+extern crate std;
+
+use super::*;
+use crate::storage::{LegacyMission, ListKeyV4, MissionV1, NftV4, DAY_IN_LEDGERS};
+use soroban_sdk::testutils::storage::Persistent as _;
+use soroban_sdk::testutils::{
+    Address as _, AuthorizedFunction, AuthorizedInvocation, Events, Ledger, MockAuth, MockAuthInvoke,
+};
+use soroban_sdk::{contract, contractimpl, symbol_short, token, vec, Address, BytesN, Env, IntoVal, Map, String, Symbol, Val, Vec};
+
+const DEADLINE: u64 = 10_000;
+
+struct Setup<'a> {
+    env: Env,
+    contract: MissionFundContractClient<'a>,
+    token: token::Client<'a>,
+    token_admin: token::StellarAssetClient<'a>,
+    admin: Address,
+    owner: Address,
+}
+
+fn setup<'a>() -> Setup<'a> {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let sac = env.register_stellar_asset_contract_v2(admin.clone());
+    let token = token::Client::new(&env, &sac.address());
+    let token_admin = token::StellarAssetClient::new(&env, &sac.address());
+
+    let contract_id = env.register(MissionFundContract, ());
+    let contract = MissionFundContractClient::new(&env, &contract_id);
+    contract.initialize(&admin, &sac.address());
+
+    let owner = Address::generate(&env);
+    Setup {
+        env,
+        contract,
+        token,
+        token_admin,
+        admin,
+        owner,
+    }
+}
+
+fn register(s: &Setup, seed: u8, target: i128) -> BytesN<32> {
+    register_with_milestones(s, seed, target, Vec::new(&s.env), None)
+}
+
+fn register_with_deadline(s: &Setup, seed: u8, target: i128, deadline: u64) -> BytesN<32> {
+    register_full(s, seed, target, deadline, Vec::new(&s.env), None)
+}
+
+fn register_with_milestones(
+    s: &Setup,
+    seed: u8,
+    target: i128,
+    milestones: Vec<MilestoneSpec>,
+    reviewer: Option<Address>,
+) -> BytesN<32> {
+    register_full(s, seed, target, DEADLINE, milestones, reviewer)
+}
+
+fn register_full(
+    s: &Setup,
+    seed: u8,
+    target: i128,
+    deadline: u64,
+    milestones: Vec<MilestoneSpec>,
+    reviewer: Option<Address>,
+) -> BytesN<32> {
+    let id = BytesN::from_array(&s.env, &[seed; 32]);
+    s.contract.register_mission(
+        &id,
+        &String::from_str(&s.env, "Lunar Rover"),
+        &String::from_str(&s.env, "Regolith sampling rover"),
+        &String::from_str(&s.env, "ipfs://rover.png"),
+        &target,
+        &deadline,
+        &s.owner,
+        &milestones,
+        &reviewer,
+        &None,
+    );
+    id
+}
+
+/// Events emitted by the mission contract itself during the last invocation,
+/// leaving out the token contract's own transfer events.
+fn contract_events(s: &Setup) -> Vec<(Address, Vec<Val>, Val)> {
+    let mut events = Vec::new(&s.env);
+    for event in s.env.events().all().iter() {
+        if event.0 == s.contract.address {
+            events.push_back(event);
+        }
+    }
+    events
+}
+
+/// Makes the next calls run with only `signer`'s authorization for
+/// `fn_name(args)`, so any other `require_auth` fails.
+fn sign_as(s: &Setup, signer: &Address, fn_name: &str, args: Vec<Val>) {
+    s.env.mock_auths(&[MockAuth {
+        address: signer,
+        invoke: &MockAuthInvoke {
+            contract: &s.contract.address,
+            fn_name,
+            args,
+            sub_invokes: &[],
+        },
+    }]);
+}
+
+fn milestone(s: &Setup, title: &str, share: u32) -> MilestoneSpec {
+    MilestoneSpec {
+        title: String::from_str(&s.env, title),
+        share,
+    }
+}
+
+#[test]
+fn fund_mission_moves_tokens_into_custody() {
+    let s = setup();
+    let id = register(&s, 1, 1_000);
+    let donor = Address::generate(&s.env);
+    s.token_admin.mint(&donor, &500);
+
+    s.contract.fund_mission(&id, &donor, &300);
+
+    assert_eq!(s.token.balance(&donor), 200);
+    assert_eq!(s.token.balance(&s.contract.address), 300);
+    assert_eq!(s.contract.get_mission(&id).funded_amount, 300);
+    assert_eq!(s.contract.get_remaining_funds(&id), 700);
+}
+
+#[test]
+fn fund_mission_requires_donor_auth() {
+    let s = setup();
+    let id = register(&s, 1, 1_000);
+    let donor = Address::generate(&s.env);
+    s.token_admin.mint(&donor, &500);
+
+    s.contract.fund_mission(&id, &donor, &100);
+
+    let auths = s.env.auths();
+    assert_eq!(auths.len(), 1);
+    assert_eq!(auths[0].0, donor);
+}
+
+#[test]
+#[should_panic]
+fn fund_mission_without_balance_fails() {
+    let s = setup();
+    let id = register(&s, 1, 1_000);
+    let donor = Address::generate(&s.env);
+
+    s.contract.fund_mission(&id, &donor, &100);
+}
+
+#[test]
+fn initialize_only_once() {
+    let s = setup();
+    let other = Address::generate(&s.env);
+    assert_eq!(
+        s.contract.try_initialize(&other, &other),
+        Err(Ok(Error::AlreadyInitialized))
+    );
+}
+
+#[test]
+fn register_rejects_duplicates_and_bad_targets() {
+    let s = setup();
+    let id = register(&s, 1, 1_000);
+    let title = String::from_str(&s.env, "Dup");
+    assert_eq!(
+        s.contract
+            .try_register_mission(&id, &title, &title, &title, &1_000, &DEADLINE, &s.owner, &vec![&s.env], &None, &None),
+        Err(Ok(Error::AlreadyExists))
+    );
+    let other = BytesN::from_array(&s.env, &[2; 32]);
+    assert_eq!(
+        s.contract
+            .try_register_mission(&other, &title, &title, &title, &0, &DEADLINE, &s.owner, &vec![&s.env], &None, &None),
+        Err(Ok(Error::InvalidAmount))
+    );
+}
+
+#[test]
+fn unknown_mission_is_reported() {
+    let s = setup();
+    let missing = BytesN::from_array(&s.env, &[9; 32]);
+    let donor = Address::generate(&s.env);
+    assert_eq!(
+        s.contract.try_get_mission(&missing).err(),
+        Some(Ok(Error::MissionNotFound))
+    );
+    assert_eq!(
+        s.contract.try_get_remaining_funds(&missing),
+        Err(Ok(Error::MissionNotFound))
+    );
+    assert_eq!(
+        s.contract.try_fund_mission(&missing, &donor, &10),
+        Err(Ok(Error::MissionNotFound))
+    );
+    assert_eq!(
+        s.contract.try_get_nft_metadata(&0).err(),
+        Some(Ok(Error::NftNotFound))
+    );
+}
+
+#[test]
+fn fund_mission_rejects_non_positive_amounts() {
+    let s = setup();
+    let id = register(&s, 1, 1_000);
+    let donor = Address::generate(&s.env);
+    s.token_admin.mint(&donor, &500);
+    assert_eq!(
+        s.contract.try_fund_mission(&id, &donor, &0),
+        Err(Ok(Error::InvalidAmount))
+    );
+    assert_eq!(
+        s.contract.try_fund_mission(&id, &donor, &-5),
+        Err(Ok(Error::InvalidAmount))
+    );
+    assert_eq!(s.token.balance(&donor), 500);
+}
+
+#[test]
+fn each_donation_mints_a_distinct_nft() {
+    let s = setup();
+    let id = register(&s, 1, 1_000);
+    let alice = Address::generate(&s.env);
+    let bob = Address::generate(&s.env);
+    s.token_admin.mint(&alice, &500);
+    s.token_admin.mint(&bob, &500);
+
+    let first = s.contract.fund_mission(&id, &alice, &100);
+    let second = s.contract.fund_mission(&id, &bob, &200);
+    let third = s.contract.fund_mission(&id, &alice, &50);
+
+    assert_eq!((first, second, third), (0, 1, 2));
+    assert_eq!(s.contract.total_supply(), 3);
+    assert_eq!(s.contract.owner_of(&second), bob);
+    assert_eq!(s.contract.balance_of(&alice), 2);
+    assert_eq!(s.contract.tokens_of(&alice, &0, &10), vec![&s.env, 0, 2]);
+
+    assert_eq!(contribution_of(&s, second), 200);
+    let contrib: i128 = nft_attribute(&s.contract.get_nft_metadata(&third), 1)
+        .get(Symbol::new(&s.env, "value"))
+        .unwrap()
+        .into_val(&s.env);
+    assert_eq!(contrib, 50);
+}
+
+/// The `index`-th `{trait_type, value}` pair of a token's metadata.
+fn nft_attribute(metadata: &Map<Symbol, Val>, index: u32) -> Map<Symbol, Val> {
+    let env = metadata.env();
+    let attributes: Vec<Map<Symbol, Val>> = metadata
+        .get(Symbol::new(env, "attributes"))
+        .unwrap()
+        .into_val(env);
+    attributes.get(index).unwrap()
+}
+
+fn contribution_of(s: &Setup, token_id: u64) -> i128 {
+    let attribute = nft_attribute(&s.contract.token_metadata(&token_id), 1);
+    let trait_type: String = attribute
+        .get(Symbol::new(&s.env, "trait_type"))
+        .unwrap()
+        .into_val(&s.env);
+    assert_eq!(trait_type, String::from_str(&s.env, "Contribution"));
+    attribute
+        .get(Symbol::new(&s.env, "value"))
+        .unwrap()
+        .into_val(&s.env)
+}
+
+#[test]
+fn nft_transfer_and_approval() {
+    let s = setup();
+    let id = register(&s, 1, 1_000);
+    let alice = Address::generate(&s.env);
+    let bob = Address::generate(&s.env);
+    let carol = Address::generate(&s.env);
+    s.token_admin.mint(&alice, &500);
+    let token_id = s.contract.fund_mission(&id, &alice, &100);
+
+    s.contract.transfer(&alice, &bob, &token_id);
+    assert_eq!(s.contract.owner_of(&token_id), bob);
+    assert_eq!(s.contract.balance_of(&alice), 0);
+    assert_eq!(s.contract.tokens_of(&bob, &0, &10), vec![&s.env, token_id]);
+
+    s.contract.approve(&bob, &Some(carol.clone()), &token_id);
+    assert_eq!(s.contract.get_approved(&token_id), Some(carol.clone()));
+    s.contract.transfer_from(&carol, &bob, &alice, &token_id);
+    assert_eq!(s.contract.owner_of(&token_id), alice);
+    assert_eq!(s.contract.get_approved(&token_id), None);
+}
+
+#[test]
+fn token_lists_stay_dense_across_transfers() {
+    let s = setup();
+    let id = register(&s, 1, 1_000);
+    let alice = Address::generate(&s.env);
+    let bob = Address::generate(&s.env);
+    s.token_admin.mint(&alice, &500);
+    for _ in 0..4 {
+        s.contract.fund_mission(&id, &alice, &10);
+    }
+
+    // The last token fills the gap the first one leaves.
+    s.contract.transfer(&alice, &bob, &0);
+    assert_eq!(s.contract.balance_of(&alice), 3);
+    assert_eq!(s.contract.tokens_of(&alice, &0, &10), vec![&s.env, 3, 1, 2]);
+    s.contract.transfer(&alice, &bob, &2);
+    assert_eq!(s.contract.tokens_of(&alice, &0, &10), vec![&s.env, 3, 1]);
+    assert_eq!(s.contract.tokens_of(&alice, &1, &10), vec![&s.env, 1]);
+    assert_eq!(s.contract.tokens_of(&bob, &0, &10), vec![&s.env, 0, 2]);
+
+    s.contract.transfer(&bob, &alice, &0);
+    s.contract.transfer(&alice, &bob, &3);
+    assert_eq!(s.contract.tokens_of(&alice, &0, &10), vec![&s.env, 0, 1]);
+    assert_eq!(s.contract.tokens_of(&bob, &0, &10), vec![&s.env, 2, 3]);
+}
+
+#[test]
+fn nft_metadata_follows_the_wallet_schema() {
+    let s = setup();
+    let id = register(&s, 0xab, 1_000);
+    let donor = Address::generate(&s.env);
+    s.token_admin.mint(&donor, &100);
+    let token_id = s.contract.fund_mission(&id, &donor, &100);
+
+    let metadata = s.contract.token_metadata(&token_id);
+    let text = |key: &str| -> String {
+        metadata.get(Symbol::new(&s.env, key)).unwrap().into_val(&s.env)
+    };
+    assert_eq!(text("name"), String::from_str(&s.env, "Lunar Rover"));
+    assert_eq!(text("description"), String::from_str(&s.env, "Regolith sampling rover"));
+    assert_eq!(text("image"), String::from_str(&s.env, "ipfs://rover.png"));
+    let mission_hex: String = nft_attribute(&metadata, 0)
+        .get(Symbol::new(&s.env, "value"))
+        .unwrap()
+        .into_val(&s.env);
+    assert_eq!(mission_hex, String::from_str(&s.env, &"ab".repeat(32)));
+    assert_eq!(contribution_of(&s, token_id), 100);
+}
+
+#[test]
+fn token_uri_uses_hex_ids_under_the_base_uri() {
+    let s = setup();
+    let id = register(&s, 0x0f, 1_000);
+    let donor = Address::generate(&s.env);
+    s.token_admin.mint(&donor, &500);
+    for _ in 0..11 {
+        s.contract.fund_mission(&id, &donor, &10);
+    }
+
+    let expected = std::format!(
+        "ipfs://astrofi-nft/mission/{}/000000000000000a.json",
+        "0f".repeat(32)
+    );
+    assert_eq!(s.contract.token_uri(&10), String::from_str(&s.env, &expected));
+    assert_eq!(s.contract.try_token_uri(&11), Err(Ok(Error::NftNotFound)));
+
+    s.contract.set_base_uri(&String::from_str(&s.env, "https://nft.astrofi.space"));
+    let expected = std::format!(
+        "https://nft.astrofi.space/mission/{}/0000000000000000.json",
+        "0f".repeat(32)
+    );
+    assert_eq!(s.contract.token_uri(&0), String::from_str(&s.env, &expected));
+
+    let too_long = String::from_str(&s.env, &"x".repeat(nft::MAX_BASE_URI_LEN as usize + 1));
+    assert_eq!(s.contract.try_set_base_uri(&too_long), Err(Ok(Error::InvalidConfig)));
+}
+
+#[test]
+fn nft_transfer_by_non_owner_fails() {
+    let s = setup();
+    let id = register(&s, 1, 1_000);
+    let alice = Address::generate(&s.env);
+    let mallory = Address::generate(&s.env);
+    s.token_admin.mint(&alice, &500);
+    let token_id = s.contract.fund_mission(&id, &alice, &100);
+
+    assert_eq!(
+        s.contract.try_transfer(&mallory, &mallory, &token_id),
+        Err(Ok(Error::NotOwner))
+    );
+}
+
+#[test]
+fn nft_transfer_from_requires_approval() {
+    let s = setup();
+    let id = register(&s, 1, 1_000);
+    let alice = Address::generate(&s.env);
+    let mallory = Address::generate(&s.env);
+    s.token_admin.mint(&alice, &500);
+    let token_id = s.contract.fund_mission(&id, &alice, &100);
+
+    assert_eq!(
+        s.contract.try_transfer_from(&mallory, &alice, &mallory, &token_id),
+        Err(Ok(Error::NotApproved))
+    );
+}
+
+#[test]
+fn migrate_legacy_missions_moves_records_into_typed_keys() {
+    let s = setup();
+    let id = BytesN::from_array(&s.env, &[7; 32]);
+    let donor = Address::generate(&s.env);
+    let legacy = LegacyMission {
+        title: String::from_str(&s.env, "Legacy"),
+        desc: String::from_str(&s.env, "Pre-DataKey mission"),
+        image_url: String::from_str(&s.env, "ipfs://legacy.png"),
+        target_amount: 1_000,
+        funded_amount: 40,
+        owner: s.owner.clone(),
+    };
+    s.env.as_contract(&s.contract.address, || {
+        let mut meta: Map<Symbol, Val> = Map::new(&s.env);
+        meta.set(symbol_short!("owner"), donor.into_val(&s.env));
+        meta.set(symbol_short!("contrib"), 40_i128.into_val(&s.env));
+        let nft_key = s.env.crypto().sha256(&id.clone().into()).to_bytes();
+        s.env.storage().persistent().set(&id, &legacy);
+        s.env.storage().persistent().set(&nft_key, &meta);
+    });
+
+    let title = String::from_str(&s.env, "Squatter");
+    assert_eq!(
+        s.contract
+            .try_register_mission(&id, &title, &title, &title, &10, &DEADLINE, &s.owner, &vec![&s.env], &None, &None),
+        Err(Ok(Error::AlreadyExists))
+    );
+
+    let ids = vec![&s.env, id.clone(), BytesN::from_array(&s.env, &[8; 32])];
+    assert_eq!(s.contract.migrate_legacy_missions(&ids, &DEADLINE), 1);
+    assert_eq!(s.contract.migrate_legacy_missions(&ids, &DEADLINE), 0);
+
+    assert_eq!(s.contract.get_remaining_funds(&id), 1_000);
+    assert_eq!(s.contract.list_missions(&0, &10).get(0).unwrap().0, id);
+    assert_eq!(s.contract.tokens_of(&donor, &0, &10), vec![&s.env, 0]);
+    assert_eq!(contribution_of(&s, 0), 40);
+    s.env.as_contract(&s.contract.address, || {
+        assert!(!s.env.storage().persistent().has(&id));
+    });
+}
+
+/// The original contract never took custody of donations, so a migrated
+/// mission must not pay out its old total from other missions' deposits.
+#[test]
+fn migrated_legacy_totals_are_not_paid_out() {
+    let s = setup();
+    let id = BytesN::from_array(&s.env, &[7; 32]);
+    let legacy = LegacyMission {
+        title: String::from_str(&s.env, "Legacy"),
+        desc: String::from_str(&s.env, "Pre-DataKey mission"),
+        image_url: String::from_str(&s.env, "ipfs://legacy.png"),
+        target_amount: 1_000,
+        funded_amount: 400,
+        owner: s.owner.clone(),
+    };
+    s.env.as_contract(&s.contract.address, || {
+        s.env.storage().persistent().set(&id, &legacy);
+    });
+    s.contract.migrate_legacy_missions(&vec![&s.env, id.clone()], &DEADLINE);
+    assert_eq!(
+        contract_events(&s),
+        vec![
+            &s.env,
+            (
+                s.contract.address.clone(),
+                (symbol_short!("mission"), symbol_short!("migrate"), id.clone()).into_val(&s.env),
+                400_i128.into_val(&s.env),
+            ),
+        ]
+    );
+    let migrated = s.contract.get_mission(&id);
+    assert_eq!(migrated.funded_amount, 0);
+    assert_eq!(migrated.funds.get(s.token.address.clone()).unwrap().held, 0);
+
+    let neighbour = register(&s, 1, 500);
+    let donor = Address::generate(&s.env);
+    s.token_admin.mint(&donor, &1_600);
+    s.contract.fund_mission(&neighbour, &donor, &500);
+    s.contract.fund_mission(&id, &donor, &600);
+    assert_eq!(s.contract.try_withdraw(&id), Err(Ok(Error::NotFunded)));
+
+    s.contract.fund_mission(&id, &donor, &400);
+    assert_eq!(s.contract.withdraw(&id), 1_000);
+    assert_eq!(s.token.balance(&s.owner), 1_000);
+    assert_eq!(s.token.balance(&s.contract.address), 500);
+    assert_eq!(s.contract.withdraw(&neighbour), 500);
+    assert_eq!(s.token.balance(&s.contract.address), 0);
+}
+
+/// Legacy missions had no deadline; they take the one they are migrated
+/// with, and past it an unfunded one refunds its donors.
+#[test]
+fn migrated_legacy_missions_expire_into_refunds() {
+    let s = setup();
+    let id = BytesN::from_array(&s.env, &[7; 32]);
+    let legacy = LegacyMission {
+        title: String::from_str(&s.env, "Legacy"),
+        desc: String::from_str(&s.env, "Pre-DataKey mission"),
+        image_url: String::from_str(&s.env, "ipfs://legacy.png"),
+        target_amount: 1_000,
+        funded_amount: 0,
+        owner: s.owner.clone(),
+    };
+    s.env.as_contract(&s.contract.address, || {
+        s.env.storage().persistent().set(&id, &legacy);
+    });
+    let ids = vec![&s.env, id.clone()];
+    s.env.ledger().with_mut(|li| li.timestamp = 100);
+    assert_eq!(
+        s.contract.try_migrate_legacy_missions(&ids, &100),
+        Err(Ok(Error::InvalidDeadline))
+    );
+    s.contract.migrate_legacy_missions(&ids, &DEADLINE);
+    assert_eq!(s.contract.get_mission(&id).deadline, DEADLINE);
+
+    let donor = donate(&s, &id, 300);
+    assert_eq!(
+        s.contract.try_claim_refund(&id, &donor),
+        Err(Ok(Error::RefundUnavailable))
+    );
+    s.env.ledger().with_mut(|li| li.timestamp = DEADLINE + 1);
+    assert_eq!(s.contract.get_mission_status(&id), MissionStatus::Expired);
+    assert_eq!(
+        s.contract.try_fund_mission(&id, &donor, &10),
+        Err(Ok(Error::DeadlinePassed))
+    );
+    assert_eq!(s.contract.claim_refund(&id, &donor), 300);
+    assert_eq!(s.token.balance(&donor), 300);
+}
+
+#[test]
+fn register_validates_milestone_plan() {
+    let s = setup();
+    let reviewer = Address::generate(&s.env);
+    let title = String::from_str(&s.env, "Bad plan");
+    let id = BytesN::from_array(&s.env, &[3; 32]);
+    let short = vec![&s.env, milestone(&s, "Design", 40), milestone(&s, "Build", 50)];
+    assert_eq!(
+        s.contract.try_register_mission(
+            &id,
+            &title,
+            &title,
+            &title,
+            &1_000,
+            &DEADLINE,
+            &s.owner,
+            &short,
+            &Some(reviewer),
+            &None
+        ),
+        Err(Ok(Error::InvalidMilestones))
+    );
+    let full = vec![&s.env, milestone(&s, "Design", 100)];
+    assert_eq!(
+        s.contract
+            .try_register_mission(&id, &title, &title, &title, &1_000, &DEADLINE, &s.owner, &full, &None, &None),
+        Err(Ok(Error::InvalidMilestones))
+    );
+}
+
+#[test]
+fn approved_milestones_release_their_share_to_owner() {
+    let s = setup();
+    let reviewer = Address::generate(&s.env);
+    let plan = vec![
+        &s.env,
+        milestone(&s, "Design", 30),
+        milestone(&s, "Build", 30),
+        milestone(&s, "Launch", 40),
+    ];
+    let id = register_with_milestones(&s, 1, 1_000, plan, Some(reviewer.clone()));
+    let donor = Address::generate(&s.env);
+    s.token_admin.mint(&donor, &1_001);
+    s.contract.fund_mission(&id, &donor, &1_001);
+
+    let evidence = BytesN::from_array(&s.env, &[0xab; 32]);
+    s.contract.submit_milestone(&id, &0, &evidence);
+    assert_eq!(
+        s.contract.get_milestone_plan(&id).milestones.get(0).unwrap().status,
+        MilestoneStatus::UnderReview
+    );
+    assert_eq!(s.contract.review_milestone(&id, &0, &true), 300);
+    assert_eq!(s.token.balance(&s.owner), 300);
+
+    s.contract.submit_milestone(&id, &2, &evidence);
+    assert_eq!(s.contract.review_milestone(&id, &2, &false), 0);
+    s.contract.submit_milestone(&id, &2, &evidence);
+    assert_eq!(s.contract.review_milestone(&id, &2, &true), 400);
+
+    // The final milestone sweeps the rounding remainder.
+    s.contract.submit_milestone(&id, &1, &evidence);
+    assert_eq!(s.contract.review_milestone(&id, &1, &true), 301);
+    assert_eq!(s.token.balance(&s.owner), 1_001);
+    assert_eq!(s.token.balance(&s.contract.address), 0);
+
+    let plan = s.contract.get_milestone_plan(&id);
+    assert_eq!(plan.released, 1_001);
+    assert_eq!(plan.milestones.get(1).unwrap().status, MilestoneStatus::Completed);
+    assert_eq!(plan.milestones.get(0).unwrap().evidence, vec![&s.env, evidence.clone()]);
+    assert_eq!(plan.milestones.get(2).unwrap().evidence.len(), 2);
+}
+
+#[test]
+fn donations_stop_once_every_milestone_is_released() {
+    let s = setup();
+    let reviewer = Address::generate(&s.env);
+    let plan = vec![&s.env, milestone(&s, "Everything", 100)];
+    let id = register_with_milestones(&s, 1, 1_000, plan, Some(reviewer));
+    let donor = Address::generate(&s.env);
+    s.token_admin.mint(&donor, &1_500);
+    s.contract.fund_mission(&id, &donor, &1_000);
+    s.contract
+        .submit_milestone(&id, &0, &BytesN::from_array(&s.env, &[0xab; 32]));
+    s.contract.review_milestone(&id, &0, &true);
+
+    // Nothing could ever release a later donation from escrow.
+    assert_eq!(
+        s.contract.try_fund_mission(&id, &donor, &500),
+        Err(Ok(Error::MilestonesCompleted))
+    );
+    assert_eq!(
+        s.contract
+            .try_create_pledge(&id, &donor, &s.token.address, &100, &1_000, &200),
+        Err(Ok(Error::MilestonesCompleted))
+    );
+    assert_eq!(s.token.balance(&donor), 500);
+    assert_eq!(s.token.balance(&s.contract.address), 0);
+}
+
+#[test]
+fn milestone_submission_requires_funded_mission() {
+    let s = setup();
+    let reviewer = Address::generate(&s.env);
+    let plan = vec![&s.env, milestone(&s, "Everything", 100)];
+    let id = register_with_milestones(&s, 1, 1_000, plan, Some(reviewer));
+    let evidence = BytesN::from_array(&s.env, &[1; 32]);
+
+    assert_eq!(
+        s.contract.try_submit_milestone(&id, &0, &evidence),
+        Err(Ok(Error::NotFunded))
+    );
+
+    let donor = Address::generate(&s.env);
+    s.token_admin.mint(&donor, &1_000);
+    s.contract.fund_mission(&id, &donor, &1_000);
+    assert_eq!(
+        s.contract.try_review_milestone(&id, &0, &true),
+        Err(Ok(Error::InvalidMilestoneStatus))
+    );
+    assert_eq!(
+        s.contract.try_submit_milestone(&id, &1, &evidence),
+        Err(Ok(Error::MilestoneNotFound))
+    );
+}
+
+#[test]
+fn milestone_review_requires_reviewer_auth() {
+    let s = setup();
+    let reviewer = Address::generate(&s.env);
+    let plan = vec![&s.env, milestone(&s, "Everything", 100)];
+    let id = register_with_milestones(&s, 1, 100, plan, Some(reviewer.clone()));
+    let donor = Address::generate(&s.env);
+    s.token_admin.mint(&donor, &100);
+    s.contract.fund_mission(&id, &donor, &100);
+    s.contract
+        .submit_milestone(&id, &0, &BytesN::from_array(&s.env, &[1; 32]));
+
+    s.contract.review_milestone(&id, &0, &true);
+    let auths = s.env.auths();
+    assert_eq!(auths.len(), 1);
+    assert_eq!(auths[0].0, reviewer);
+}
+
+#[test]
+fn register_rejects_past_deadline() {
+    let s = setup();
+    s.env.ledger().set_timestamp(DEADLINE);
+    let id = BytesN::from_array(&s.env, &[1; 32]);
+    let title = String::from_str(&s.env, "Late");
+    assert_eq!(
+        s.contract.try_register_mission(
+            &id,
+            &title,
+            &title,
+            &title,
+            &1_000,
+            &DEADLINE,
+            &s.owner,
+            &vec![&s.env],
+            &None,
+            &None
+        ),
+        Err(Ok(Error::InvalidDeadline))
+    );
+}
+
+#[test]
+fn funding_closes_at_deadline() {
+    let s = setup();
+    let id = register(&s, 1, 1_000);
+    let donor = Address::generate(&s.env);
+    s.token_admin.mint(&donor, &500);
+
+    s.env.ledger().set_timestamp(DEADLINE);
+    s.contract.fund_mission(&id, &donor, &100);
+    s.env.ledger().set_timestamp(DEADLINE + 1);
+    assert_eq!(
+        s.contract.try_fund_mission(&id, &donor, &100),
+        Err(Ok(Error::DeadlinePassed))
+    );
+}
+
+#[test]
+fn failed_mission_refunds_each_donor_exactly() {
+    let s = setup();
+    let id = register(&s, 1, 1_000);
+    let alice = Address::generate(&s.env);
+    let bob = Address::generate(&s.env);
+    s.token_admin.mint(&alice, &500);
+    s.token_admin.mint(&bob, &500);
+    s.contract.fund_mission(&id, &alice, &100);
+    s.contract.fund_mission(&id, &alice, &150);
+    s.contract.fund_mission(&id, &bob, &300);
+    assert_eq!(s.contract.get_contribution(&id, &alice).amount, 250);
+
+    assert_eq!(
+        s.contract.try_claim_refund(&id, &alice),
+        Err(Ok(Error::RefundUnavailable))
+    );
+    assert_eq!(s.contract.try_withdraw(&id), Err(Ok(Error::NotFunded)));
+
+    s.env.ledger().set_timestamp(DEADLINE + 1);
+    assert_eq!(s.contract.claim_refund(&id, &alice), 250);
+    assert_eq!(
+        s.contract.try_claim_refund(&id, &alice),
+        Err(Ok(Error::NothingToClaim))
+    );
+    assert_eq!(s.contract.claim_refund(&id, &bob), 300);
+
+    assert_eq!(s.token.balance(&alice), 500);
+    assert_eq!(s.token.balance(&bob), 500);
+    assert_eq!(s.token.balance(&s.contract.address), 0);
+    assert_eq!(s.contract.get_mission(&id).funded_amount, 0);
+}
+
+#[test]
+fn successful_mission_pays_owner_and_blocks_refunds() {
+    let s = setup();
+    let id = register(&s, 1, 1_000);
+    let donor = Address::generate(&s.env);
+    s.token_admin.mint(&donor, &1_500);
+    s.contract.fund_mission(&id, &donor, &1_000);
+
+    assert_eq!(s.contract.withdraw(&id), 1_000);
+    assert_eq!(s.contract.try_withdraw(&id), Err(Ok(Error::NothingToClaim)));
+    s.contract.fund_mission(&id, &donor, &200);
+    assert_eq!(s.contract.withdraw(&id), 200);
+    assert_eq!(s.token.balance(&s.owner), 1_200);
+
+    s.env.ledger().set_timestamp(DEADLINE + 1);
+    assert_eq!(
+        s.contract.try_claim_refund(&id, &donor),
+        Err(Ok(Error::RefundUnavailable))
+    );
+}
+
+#[test]
+fn milestone_missions_cannot_withdraw_directly() {
+    let s = setup();
+    let reviewer = Address::generate(&s.env);
+    let plan = vec![&s.env, milestone(&s, "Everything", 100)];
+    let id = register_with_milestones(&s, 1, 100, plan, Some(reviewer));
+    let donor = Address::generate(&s.env);
+    s.token_admin.mint(&donor, &100);
+    s.contract.fund_mission(&id, &donor, &100);
+
+    assert_eq!(s.contract.try_withdraw(&id), Err(Ok(Error::FundsInEscrow)));
+}
+
+#[test]
+fn registering_and_funding_emit_events() {
+    let s = setup();
+    let id = register(&s, 1, 1_000);
+    assert_eq!(
+        contract_events(&s),
+        vec![
+            &s.env,
+            (
+                s.contract.address.clone(),
+                (symbol_short!("mission"), symbol_short!("register"), id.clone()).into_val(&s.env),
+                (s.owner.clone(), 1_000_i128, DEADLINE).into_val(&s.env),
+            ),
+        ]
+    );
+
+    let donor = Address::generate(&s.env);
+    s.token_admin.mint(&donor, &2_000);
+    s.contract.fund_mission(&id, &donor, &400);
+    assert_eq!(
+        contract_events(&s),
+        vec![
+            &s.env,
+            (
+                s.contract.address.clone(),
+                (symbol_short!("mission"), symbol_short!("funded"), id.clone()).into_val(&s.env),
+                (donor.clone(), 400_i128, 400_i128).into_val(&s.env),
+            ),
+            (
+                s.contract.address.clone(),
+                (symbol_short!("nft"), symbol_short!("mint"), 0_u64).into_val(&s.env),
+                (donor.clone(), id.clone()).into_val(&s.env),
+            ),
+        ]
+    );
+
+    s.contract.fund_mission(&id, &donor, &600);
+    assert_eq!(
+        contract_events(&s).slice(1..2),
+        vec![
+            &s.env,
+            (
+                s.contract.address.clone(),
+                (symbol_short!("mission"), symbol_short!("target"), id.clone()).into_val(&s.env),
+                (1_000_i128, 1_000_i128).into_val(&s.env),
+            ),
+        ]
+    );
+
+    // Crossing the target is announced once, not on every later donation.
+    s.contract.fund_mission(&id, &donor, &1);
+    assert_eq!(contract_events(&s).len(), 2);
+
+    s.contract.withdraw(&id);
+    assert_eq!(
+        contract_events(&s),
+        vec![
+            &s.env,
+            (
+                s.contract.address.clone(),
+                (symbol_short!("mission"), symbol_short!("withdraw"), id.clone()).into_val(&s.env),
+                (s.owner.clone(), 1_001_i128).into_val(&s.env),
+            ),
+        ]
+    );
+}
+
+#[test]
+fn refund_and_nft_moves_emit_events() {
+    let s = setup();
+    let id = register(&s, 1, 1_000);
+    let alice = Address::generate(&s.env);
+    let bob = Address::generate(&s.env);
+    s.token_admin.mint(&alice, &100);
+    let token_id = s.contract.fund_mission(&id, &alice, &100);
+
+    s.contract.approve(&alice, &Some(bob.clone()), &token_id);
+    assert_eq!(
+        contract_events(&s),
+        vec![
+            &s.env,
+            (
+                s.contract.address.clone(),
+                (symbol_short!("nft"), symbol_short!("approve"), token_id).into_val(&s.env),
+                (alice.clone(), Some(bob.clone())).into_val(&s.env),
+            ),
+        ]
+    );
+
+    s.contract.transfer_from(&bob, &alice, &bob, &token_id);
+    assert_eq!(
+        contract_events(&s),
+        vec![
+            &s.env,
+            (
+                s.contract.address.clone(),
+                (symbol_short!("nft"), symbol_short!("transfer"), token_id).into_val(&s.env),
+                (alice.clone(), bob.clone()).into_val(&s.env),
+            ),
+        ]
+    );
+
+    s.env.ledger().set_timestamp(DEADLINE + 1);
+    s.contract.claim_refund(&id, &alice);
+    assert_eq!(
+        contract_events(&s),
+        vec![
+            &s.env,
+            (
+                s.contract.address.clone(),
+                (symbol_short!("mission"), symbol_short!("refund"), id.clone()).into_val(&s.env),
+                (alice.clone(), 100_i128).into_val(&s.env),
+            ),
+        ]
+    );
+}
+
+#[test]
+fn milestone_flow_emits_events() {
+    let s = setup();
+    let reviewer = Address::generate(&s.env);
+    let plan = vec![&s.env, milestone(&s, "Everything", 100)];
+    let id = register_with_milestones(&s, 1, 100, plan, Some(reviewer));
+    let donor = Address::generate(&s.env);
+    s.token_admin.mint(&donor, &100);
+    s.contract.fund_mission(&id, &donor, &100);
+    let evidence = BytesN::from_array(&s.env, &[4; 32]);
+
+    s.contract.submit_milestone(&id, &0, &evidence);
+    assert_eq!(
+        contract_events(&s),
+        vec![
+            &s.env,
+            (
+                s.contract.address.clone(),
+                (symbol_short!("milestone"), symbol_short!("submit"), id.clone()).into_val(&s.env),
+                (0_u32, evidence.clone()).into_val(&s.env),
+            ),
+        ]
+    );
+
+    s.contract.review_milestone(&id, &0, &true);
+    assert_eq!(
+        contract_events(&s),
+        vec![
+            &s.env,
+            (
+                s.contract.address.clone(),
+                (symbol_short!("milestone"), symbol_short!("review"), id.clone()).into_val(&s.env),
+                (0_u32, true, 100_i128).into_val(&s.env),
+            ),
+        ]
+    );
+}
+
+fn mission_ttl(s: &Setup, id: &BytesN<32>) -> u32 {
+    ttl(s, &DataKey::Mission(id.clone()))
+}
+
+fn ttl(s: &Setup, key: &DataKey) -> u32 {
+    s.env
+        .as_contract(&s.contract.address, || s.env.storage().persistent().get_ttl(key))
+}
+
+fn advance_ledgers(s: &Setup, ledgers: u32) {
+    s.env.ledger().with_mut(|li| li.sequence_number += ledgers);
+}
+
+/// Keeps the contract instance alive across long ledger jumps so the tests
+/// exercise persistent entries only.
+fn long_lived_instance(s: &Setup) {
+    s.contract.set_ttl_config(&TtlConfig {
+        instance_threshold: 199 * DAY_IN_LEDGERS,
+        instance_extend_to: 200 * DAY_IN_LEDGERS,
+        ..s.contract.get_ttl_config()
+    });
+}
+
+#[test]
+fn writes_and_reads_extend_mission_ttl() {
+    let s = setup();
+    long_lived_instance(&s);
+    let id = register(&s, 1, 1_000);
+    let config = s.contract.get_ttl_config();
+    assert_eq!(mission_ttl(&s, &id), config.persistent_extend_to);
+
+    // Below the threshold a plain read pushes the entry back out.
+    advance_ledgers(&s, 100 * DAY_IN_LEDGERS);
+    assert_eq!(mission_ttl(&s, &id), 20 * DAY_IN_LEDGERS);
+    s.contract.get_mission(&id);
+    assert_eq!(mission_ttl(&s, &id), config.persistent_extend_to);
+}
+
+#[test]
+fn bump_mission_keeps_long_running_missions_live() {
+    let s = setup();
+    long_lived_instance(&s);
+    let reviewer = Address::generate(&s.env);
+    let attestor = MockAttestorClient::new(&s.env, &s.env.register(MockAttestor, ()));
+    let id = BytesN::from_array(&s.env, &[1; 32]);
+    let title = String::from_str(&s.env, "Lunar Rover");
+    s.contract.register_mission(
+        &id,
+        &title,
+        &title,
+        &title,
+        &1_000,
+        &DEADLINE,
+        &s.owner,
+        &vec![&s.env, milestone(&s, "Everything", 100)],
+        &Some(reviewer),
+        &Some(attestor.address.clone()),
+    );
+    let (round, _) = open_round(&s, 100, 0, DEADLINE);
+    s.contract.join_round(&round, &id);
+    // A partner that fails leaves its notification pending.
+    partner(&s, &id).fail();
+
+    let [first, second] = [Address::generate(&s.env), Address::generate(&s.env)];
+    for donor in [&first, &second] {
+        attestor.set_verified(donor, &true);
+        s.token_admin.mint(donor, &600);
+    }
+    s.contract.fund_mission(&id, &first, &400);
+    s.contract
+        .update_mission_metadata(&id, &title, &title, &String::from_str(&s.env, "ipfs://new"));
+    s.contract
+        .create_pledge(&id, &first, &s.token.address, &100, &1_000, &200);
+    s.contract.fund_mission(&id, &second, &600);
+    let signer = Address::generate(&s.env);
+    s.contract.set_team(&id, &vec![&s.env, signer.clone()], &1, &s.owner);
+    s.contract.propose(&id, &signer, &TeamAction::Close);
+
+    s.env.set_auths(&[]);
+    for _ in 0..4 {
+        advance_ledgers(&s, 100 * DAY_IN_LEDGERS);
+        s.contract.bump_mission(&id);
+        assert_eq!(s.contract.bump_donors(&id, &0, &10), 2);
+        assert_eq!(s.contract.bump_proposals(&id, &0, &10), 1);
+    }
+    // Nobody had to authorize the bumps, and every record of the mission
+    // is back at the full TTL.
+    assert_eq!(s.env.auths().len(), 0);
+    let mut keys = vec![
+        &s.env,
+        DataKey::Mission(id.clone()),
+        DataKey::Milestones(id.clone()),
+        DataKey::MetadataChanges(id.clone()),
+        DataKey::DonorCount(id.clone()),
+        DataKey::MissionRounds(id.clone()),
+        DataKey::Team(id.clone()),
+        DataKey::ProposalCount(id.clone()),
+        DataKey::Proposal(id.clone(), 0),
+        DataKey::Attestor(id.clone()),
+        DataKey::TargetCallback(id.clone()),
+        DataKey::PendingCallback(id.clone()),
+        DataKey::Pledge(id.clone(), first.clone()),
+    ];
+    for (position, donor) in [first, second].into_iter().enumerate() {
+        keys.push_back(DataKey::DonorAt(id.clone(), position as u32));
+        keys.push_back(DataKey::Contribution(id.clone(), donor));
+    }
+    for key in keys.iter() {
+        assert_eq!(ttl(&s, &key), 120 * DAY_IN_LEDGERS);
+    }
+    s.env.mock_all_auths();
+    assert_eq!(s.contract.get_mission(&id).funded_amount, 1_000);
+    assert_eq!(s.contract.get_milestone_plan(&id).milestones.len(), 1);
+
+    let missing = BytesN::from_array(&s.env, &[9; 32]);
+    assert_eq!(
+        s.contract.try_bump_mission(&missing),
+        Err(Ok(Error::MissionNotFound))
+    );
+    assert_eq!(
+        s.contract.try_bump_donors(&missing, &0, &10),
+        Err(Ok(Error::MissionNotFound))
+    );
+    assert_eq!(
+        s.contract.try_bump_proposals(&missing, &0, &10),
+        Err(Ok(Error::MissionNotFound))
+    );
+}
+
+#[test]
+fn ttl_config_is_admin_configurable() {
+    let s = setup();
+    let custom = TtlConfig {
+        persistent_threshold: 10 * DAY_IN_LEDGERS,
+        persistent_extend_to: 60 * DAY_IN_LEDGERS,
+        instance_threshold: DAY_IN_LEDGERS,
+        instance_extend_to: 10 * DAY_IN_LEDGERS,
+    };
+    s.contract.set_ttl_config(&custom);
+    assert_eq!(s.contract.get_ttl_config(), custom);
+
+    let id = register(&s, 1, 1_000);
+    assert_eq!(mission_ttl(&s, &id), 60 * DAY_IN_LEDGERS);
+
+    let inverted = TtlConfig {
+        persistent_threshold: 60 * DAY_IN_LEDGERS,
+        persistent_extend_to: 10 * DAY_IN_LEDGERS,
+        ..custom
+    };
+    assert_eq!(
+        s.contract.try_set_ttl_config(&inverted),
+        Err(Ok(Error::InvalidConfig))
+    );
+    let too_long = TtlConfig {
+        persistent_extend_to: u32::MAX,
+        ..custom
+    };
+    assert_eq!(
+        s.contract.try_set_ttl_config(&too_long),
+        Err(Ok(Error::InvalidConfig))
+    );
+}
+
+#[test]
+fn missions_are_enumerable_and_paginated() {
+    let s = setup();
+    assert_eq!(s.contract.mission_count(), 0);
+    let ids: std::vec::Vec<BytesN<32>> = (1..=5).map(|seed| register(&s, seed, 1_000)).collect();
+
+    let other_owner = Address::generate(&s.env);
+    let other = BytesN::from_array(&s.env, &[42; 32]);
+    let title = String::from_str(&s.env, "Orbital telescope");
+    s.contract.register_mission(
+        &other,
+        &title,
+        &title,
+        &title,
+        &500,
+        &DEADLINE,
+        &other_owner,
+        &vec![&s.env],
+        &None,
+        &None,
+    );
+    assert_eq!(s.contract.mission_count(), 6);
+
+    let page = s.contract.list_missions(&1, &2);
+    assert_eq!(page.len(), 2);
+    assert_eq!(page.get(0).unwrap().0, ids[1]);
+    assert_eq!(page.get(1).unwrap().0, ids[2]);
+    assert_eq!(s.contract.list_missions(&5, &10).get(0).unwrap().0, other);
+    assert_eq!(s.contract.list_missions(&6, &10).len(), 0);
+
+    let mine = s.contract.missions_by_owner(&s.owner, &3, &10);
+    assert_eq!(mine.len(), 2);
+    assert_eq!(mine.get(1).unwrap().0, ids[4]);
+    let theirs = s.contract.missions_by_owner(&other_owner, &0, &10);
+    assert_eq!(theirs.len(), 1);
+    assert_eq!(theirs.get(0).unwrap().1.target_amount, 500);
+    assert_eq!(s.contract.missions_by_owner(&other_owner, &4, &10).len(), 0);
+}
+
+#[test]
+fn missions_by_status_tracks_the_campaign() {
+    let s = setup();
+    let funded = register(&s, 1, 100);
+    let active = register(&s, 2, 1_000);
+    let expired = register_with_deadline(&s, 3, 1_000, DEADLINE / 2);
+    let donor = Address::generate(&s.env);
+    s.token_admin.mint(&donor, &100);
+    s.contract.fund_mission(&funded, &donor, &100);
+
+    s.env.ledger().set_timestamp(DEADLINE / 2 + 1);
+    assert_eq!(s.contract.get_mission_status(&funded), MissionStatus::Funded);
+    assert_eq!(s.contract.get_mission_status(&active), MissionStatus::Active);
+    assert_eq!(s.contract.get_mission_status(&expired), MissionStatus::Expired);
+
+    let page = s.contract.missions_by_status(&MissionStatus::Active, &0, &10);
+    assert_eq!(page.missions.len(), 1);
+    assert_eq!(page.missions.get(0).unwrap().0, active);
+    assert_eq!(page.next, None);
+
+    // A limit of one stops after the first match and hands back a cursor.
+    let first = s.contract.missions_by_status(&MissionStatus::Funded, &0, &1);
+    assert_eq!(first.missions.get(0).unwrap().0, funded);
+    assert_eq!(first.next, Some(1));
+    let rest = s.contract.missions_by_status(&MissionStatus::Funded, &1, &1);
+    assert_eq!(rest.missions.len(), 0);
+    assert_eq!(rest.next, None);
+
+    s.env.ledger().set_timestamp(DEADLINE + 1);
+    let page = s.contract.missions_by_status(&MissionStatus::Expired, &0, &10);
+    assert_eq!(page.missions.len(), 2);
+}
+
+#[test]
+fn contributions_are_tracked_per_donor() {
+    let s = setup();
+    let rover = register(&s, 1, 1_000);
+    let telescope = register(&s, 2, 1_000);
+    let alice = Address::generate(&s.env);
+    let bob = Address::generate(&s.env);
+    s.token_admin.mint(&alice, &1_000);
+    s.token_admin.mint(&bob, &1_000);
+
+    s.env.ledger().set_timestamp(100);
+    s.contract.fund_mission(&rover, &alice, &100);
+    s.env.ledger().set_timestamp(200);
+    s.contract.fund_mission(&rover, &bob, &50);
+    s.env.ledger().set_timestamp(300);
+    s.contract.fund_mission(&rover, &alice, &25);
+    s.contract.fund_mission(&telescope, &alice, &10);
+
+    assert_eq!(
+        s.contract.get_contribution(&rover, &alice),
+        Contribution {
+            amount: 125,
+            refunded: 0,
+            count: 2,
+            first_at: 100,
+            last_at: 300,
+        }
+    );
+    assert_eq!(
+        s.contract.try_get_contribution(&telescope, &bob),
+        Err(Ok(Error::NotContributor))
+    );
+
+    assert_eq!(s.contract.donor_count(&rover), 2);
+    let donors = s.contract.list_donors(&rover, &0, &10);
+    assert_eq!(donors.len(), 2);
+    assert_eq!(donors.get(0).unwrap().0, alice);
+    assert_eq!(donors.get(1).unwrap().0, bob);
+    assert_eq!(donors.get(1).unwrap().1.amount, 50);
+    assert_eq!(s.contract.list_donors(&rover, &1, &1).get(0).unwrap().0, bob);
+    assert_eq!(s.contract.list_donors(&rover, &2, &10).len(), 0);
+
+    assert_eq!(
+        s.contract.donor_missions(&alice, &0, &10),
+        vec![&s.env, rover.clone(), telescope.clone()]
+    );
+    assert_eq!(s.contract.donor_missions(&bob, &0, &10), vec![&s.env, rover]);
+    assert_eq!(s.contract.donor_missions(&alice, &1, &10), vec![&s.env, telescope]);
+}
+
+#[test]
+fn refunds_are_recorded_on_the_contribution() {
+    let s = setup();
+    let id = register(&s, 1, 1_000);
+    let donor = Address::generate(&s.env);
+    let stranger = Address::generate(&s.env);
+    s.token_admin.mint(&donor, &300);
+    s.contract.fund_mission(&id, &donor, &300);
+
+    s.env.ledger().set_timestamp(DEADLINE + 1);
+    s.contract.claim_refund(&id, &donor);
+    let record = s.contract.get_contribution(&id, &donor);
+    assert_eq!((record.amount, record.refunded, record.count), (0, 300, 1));
+    assert_eq!(
+        s.contract.try_claim_refund(&id, &stranger),
+        Err(Ok(Error::NotContributor))
+    );
+}
+
+#[test]
+fn admin_handover_takes_two_steps() {
+    let s = setup();
+    let successor = Address::generate(&s.env);
+    assert_eq!(s.contract.get_admin(), s.admin);
+    assert_eq!(s.contract.try_accept_admin(), Err(Ok(Error::NoPendingAdmin)));
+
+    s.contract.propose_admin(&successor);
+    assert_eq!(s.contract.get_pending_admin(), Some(successor.clone()));
+    assert_eq!(s.contract.get_admin(), s.admin);
+
+    s.contract.accept_admin();
+    assert_eq!(s.env.auths()[0].0, successor);
+    assert_eq!(s.contract.get_admin(), successor);
+    assert_eq!(s.contract.get_pending_admin(), None);
+
+    // The previous admin's signature no longer carries any weight.
+    sign_as(&s, &s.admin, "set_paused", (true,).into_val(&s.env));
+    assert!(s.contract.try_set_paused(&true).is_err());
+    sign_as(&s, &successor, "set_paused", (true,).into_val(&s.env));
+    s.contract.set_paused(&true);
+    assert!(s.contract.is_paused());
+}
+
+#[test]
+fn admin_entry_points_reject_other_signers() {
+    let s = setup();
+    let id = register(&s, 1, 1_000);
+    let mallory = Address::generate(&s.env);
+
+    sign_as(&s, &mallory, "set_paused", (true,).into_val(&s.env));
+    assert!(s.contract.try_set_paused(&true).is_err());
+    sign_as(&s, &mallory, "set_mission_paused", (id.clone(), true).into_val(&s.env));
+    assert!(s.contract.try_set_mission_paused(&id, &true).is_err());
+    let role_args = (Role::FeeCollector, Some(mallory.clone())).into_val(&s.env);
+    sign_as(&s, &mallory, "set_role", role_args);
+    assert!(s
+        .contract
+        .try_set_role(&Role::FeeCollector, &Some(mallory.clone()))
+        .is_err());
+    sign_as(&s, &mallory, "propose_admin", (mallory.clone(),).into_val(&s.env));
+    assert!(s.contract.try_propose_admin(&mallory).is_err());
+
+    s.env.mock_all_auths();
+    assert!(!s.contract.is_paused());
+    assert!(!s.contract.is_mission_paused(&id));
+    assert_eq!(s.contract.get_role(&Role::FeeCollector), None);
+    assert_eq!(s.contract.get_pending_admin(), None);
+}
+
+#[test]
+fn roles_are_assigned_and_revoked_by_admin() {
+    let s = setup();
+    let collector = Address::generate(&s.env);
+    s.contract.set_role(&Role::FeeCollector, &Some(collector.clone()));
+    assert_eq!(s.env.auths()[0].0, s.admin);
+    assert_eq!(s.contract.get_role(&Role::FeeCollector), Some(collector));
+    s.contract.set_role(&Role::FeeCollector, &None);
+    assert_eq!(s.contract.get_role(&Role::FeeCollector), None);
+}
+
+#[test]
+fn platform_reviewer_covers_missions_without_their_own() {
+    let s = setup();
+    let plan = vec![&s.env, milestone(&s, "Everything", 100)];
+    let id = BytesN::from_array(&s.env, &[1; 32]);
+    let title = String::from_str(&s.env, "Unreviewed");
+    assert_eq!(
+        s.contract.try_register_mission(
+            &id, &title, &title, &title, &100, &DEADLINE, &s.owner, &plan, &None, &None
+        ),
+        Err(Ok(Error::InvalidMilestones))
+    );
+
+    let reviewer = Address::generate(&s.env);
+    s.contract.set_role(&Role::Reviewer, &Some(reviewer.clone()));
+    let id = register_with_milestones(&s, 1, 100, plan, None);
+    assert_eq!(s.contract.get_milestone_plan(&id).reviewer, reviewer);
+}
+
+#[test]
+fn global_pause_blocks_funding_and_payouts_but_not_refunds() {
+    let s = setup();
+    let funded = register(&s, 1, 100);
+    let failing = register(&s, 2, 1_000);
+    let donor = Address::generate(&s.env);
+    s.token_admin.mint(&donor, &1_000);
+    s.contract.fund_mission(&funded, &donor, &100);
+    s.contract.fund_mission(&failing, &donor, &100);
+
+    s.contract.set_paused(&true);
+    assert_eq!(
+        s.contract.try_fund_mission(&funded, &donor, &1),
+        Err(Ok(Error::Paused))
+    );
+    assert_eq!(s.contract.try_withdraw(&funded), Err(Ok(Error::Paused)));
+
+    s.env.ledger().set_timestamp(DEADLINE + 1);
+    assert_eq!(s.contract.claim_refund(&failing, &donor), 100);
+
+    s.contract.set_paused(&false);
+    assert_eq!(s.contract.withdraw(&funded), 100);
+}
+
+#[test]
+fn mission_pause_only_affects_that_mission() {
+    let s = setup();
+    let paused = register(&s, 1, 100);
+    let open = register(&s, 2, 100);
+    let donor = Address::generate(&s.env);
+    s.token_admin.mint(&donor, &1_000);
+
+    s.contract.set_mission_paused(&paused, &true);
+    assert!(s.contract.is_mission_paused(&paused));
+    assert_eq!(
+        s.contract.try_fund_mission(&paused, &donor, &100),
+        Err(Ok(Error::MissionPaused))
+    );
+    s.contract.fund_mission(&open, &donor, &100);
+
+    let reviewer = Address::generate(&s.env);
+    let plan = vec![&s.env, milestone(&s, "Everything", 100)];
+    let escrowed = register_with_milestones(&s, 3, 100, plan, Some(reviewer));
+    s.contract.fund_mission(&escrowed, &donor, &100);
+    s.contract
+        .submit_milestone(&escrowed, &0, &BytesN::from_array(&s.env, &[1; 32]));
+    s.contract.set_mission_paused(&escrowed, &true);
+    assert_eq!(
+        s.contract.try_review_milestone(&escrowed, &0, &true),
+        Err(Ok(Error::MissionPaused))
+    );
+    s.contract.set_mission_paused(&escrowed, &false);
+    assert_eq!(s.contract.review_milestone(&escrowed, &0, &true), 100);
+}
+
+#[test]
+fn donations_pay_the_platform_fee_to_the_collector() {
+    let s = setup();
+    let collector = Address::generate(&s.env);
+    s.contract.set_role(&Role::FeeCollector, &Some(collector.clone()));
+    s.contract.set_fee_bps(&250);
+    let id = register(&s, 1, 1_000);
+    let donor = Address::generate(&s.env);
+    s.token_admin.mint(&donor, &1_000);
+
+    assert_eq!(
+        s.contract.get_fee_quote(&400),
+        FeeQuote {
+            amount: 400,
+            fee: 10,
+            net: 390,
+        }
+    );
+    s.contract.fund_mission(&id, &donor, &400);
+
+    assert_eq!(s.token.balance(&donor), 600);
+    assert_eq!(s.token.balance(&collector), 10);
+    assert_eq!(s.token.balance(&s.contract.address), 390);
+    let mission = s.contract.get_mission(&id);
+    assert_eq!((mission.funded_amount, mission.fees_paid), (390, 10));
+    assert_eq!(s.contract.get_contribution(&id, &donor).amount, 390);
+
+    // Refunds return the net contribution; the fee stays with the platform.
+    s.env.ledger().set_timestamp(DEADLINE + 1);
+    assert_eq!(s.contract.claim_refund(&id, &donor), 390);
+    assert_eq!(s.token.balance(&s.contract.address), 0);
+}
+
+#[test]
+fn fee_is_bounded_and_skipped_without_collector() {
+    let s = setup();
+    assert_eq!(s.contract.try_set_fee_bps(&1_001), Err(Ok(Error::FeeTooHigh)));
+    s.contract.set_fee_bps(&1_000);
+    assert_eq!(s.contract.get_fee_bps(), 1_000);
+    assert_eq!(s.contract.get_fee_quote(&100).fee, 0);
+    assert_eq!(s.contract.try_get_fee_quote(&0), Err(Ok(Error::InvalidAmount)));
+
+    let collector = Address::generate(&s.env);
+    s.contract.set_role(&Role::FeeCollector, &Some(collector));
+    assert_eq!(s.contract.get_fee_quote(&100).fee, 10);
+    // Rounds in the donor's favour.
+    assert_eq!(s.contract.get_fee_quote(&19).fee, 1);
+
+    let mallory = Address::generate(&s.env);
+    sign_as(&s, &mallory, "set_fee_bps", (0_u32,).into_val(&s.env));
+    assert!(s.contract.try_set_fee_bps(&0).is_err());
+}
+
+#[test]
+fn metadata_edits_are_logged_once_donations_exist() {
+    let s = setup();
+    let id = register(&s, 1, 1_000);
+    let title = String::from_str(&s.env, "Lunar Rover II");
+    let desc = String::from_str(&s.env, "Regolith sampling rover");
+    let image = String::from_str(&s.env, "ipfs://rover-v2.png");
+
+    s.contract.update_mission_metadata(&id, &title, &desc, &image);
+    assert!(s.contract.get_metadata_changes(&id).is_empty());
+
+    let donor = Address::generate(&s.env);
+    s.token_admin.mint(&donor, &100);
+    s.contract.fund_mission(&id, &donor, &100);
+    s.env.ledger().set_timestamp(42);
+    let fixed = String::from_str(&s.env, "Lunar Rover 2");
+    s.contract.update_mission_metadata(&id, &fixed, &desc, &image);
+
+    let mission = s.contract.get_mission(&id);
+    assert_eq!(mission.title, fixed);
+    assert_eq!(mission.image_url, image);
+    assert_eq!(
+        s.contract.get_metadata_changes(&id),
+        vec![
+            &s.env,
+            MetadataChange {
+                title,
+                desc: desc.clone(),
+                image_url: image.clone(),
+                changed_at: 42,
+            },
+        ]
+    );
+
+    for _ in 1..lifecycle::MAX_METADATA_CHANGES {
+        s.contract.update_mission_metadata(&id, &fixed, &desc, &image);
+    }
+    assert_eq!(
+        s.contract.try_update_mission_metadata(&id, &fixed, &desc, &image),
+        Err(Ok(Error::TooManyChanges))
+    );
+
+    let stranger = Address::generate(&s.env);
+    sign_as(
+        &s,
+        &stranger,
+        "close_mission",
+        (id.clone(),).into_val(&s.env),
+    );
+    assert!(s.contract.try_close_mission(&id).is_err());
+}
+
+#[test]
+fn closed_missions_stop_funding_but_still_settle() {
+    let s = setup();
+    let funded = register(&s, 1, 100);
+    let short = register(&s, 2, 1_000);
+    let donor = Address::generate(&s.env);
+    s.token_admin.mint(&donor, &500);
+    s.contract.fund_mission(&funded, &donor, &100);
+    s.contract.fund_mission(&short, &donor, &100);
+
+    s.contract.close_mission(&funded);
+    s.contract.close_mission(&short);
+    assert_eq!(s.contract.try_close_mission(&short), Err(Ok(Error::MissionClosed)));
+    assert_eq!(
+        s.contract.try_fund_mission(&short, &donor, &100),
+        Err(Ok(Error::MissionClosed))
+    );
+    assert_eq!(s.contract.get_mission_status(&short), MissionStatus::Closed);
+    assert_eq!(s.contract.get_mission_status(&funded), MissionStatus::Funded);
+
+    assert_eq!(s.contract.withdraw(&funded), 100);
+    s.env.ledger().set_timestamp(DEADLINE + 1);
+    assert_eq!(s.contract.get_mission_status(&short), MissionStatus::Expired);
+    assert_eq!(s.contract.claim_refund(&short, &donor), 100);
+}
+
+#[test]
+fn cancelled_mission_refunds_unspent_funds_pro_rata() {
+    let s = setup();
+    let reviewer = Address::generate(&s.env);
+    let plan = vec![&s.env, milestone(&s, "Design", 40), milestone(&s, "Launch", 60)];
+    let id = register_with_milestones(&s, 1, 900, plan, Some(reviewer));
+    let alice = Address::generate(&s.env);
+    let bob = Address::generate(&s.env);
+    s.token_admin.mint(&alice, &600);
+    s.token_admin.mint(&bob, &400);
+    s.contract.fund_mission(&id, &alice, &600);
+    s.contract.fund_mission(&id, &bob, &400);
+
+    let evidence = BytesN::from_array(&s.env, &[0xab; 32]);
+    s.contract.submit_milestone(&id, &0, &evidence);
+    assert_eq!(s.contract.review_milestone(&id, &0, &true), 400);
+
+    assert_eq!(s.contract.cancel_mission(&id), 600);
+    assert_eq!(
+        contract_events(&s),
+        vec![
+            &s.env,
+            (
+                s.contract.address.clone(),
+                (symbol_short!("mission"), symbol_short!("cancel"), id.clone()).into_val(&s.env),
+                600i128.into_val(&s.env),
+            ),
+        ]
+    );
+    assert_eq!(s.contract.get_mission_status(&id), MissionStatus::Cancelled);
+
+    // No need to wait for the deadline.
+    assert_eq!(s.contract.claim_refund(&id, &alice), 360);
+    assert_eq!(s.contract.claim_refund(&id, &bob), 240);
+    assert_eq!(s.token.balance(&s.contract.address), 0);
+    assert_eq!(s.contract.get_contribution(&id, &bob).refunded, 240);
+
+    assert_eq!(s.contract.try_cancel_mission(&id), Err(Ok(Error::MissionCancelled)));
+    assert_eq!(
+        s.contract.try_fund_mission(&id, &alice, &1),
+        Err(Ok(Error::MissionCancelled))
+    );
+    assert_eq!(
+        s.contract.try_submit_milestone(&id, &1, &evidence),
+        Err(Ok(Error::MissionCancelled))
+    );
+    let title = String::from_str(&s.env, "Renamed");
+    assert_eq!(
+        s.contract.try_update_mission_metadata(&id, &title, &title, &title),
+        Err(Ok(Error::MissionCancelled))
+    );
+}
+
+#[test]
+fn cancelled_refund_shares_never_exceed_the_pool() {
+    let s = setup();
+    let id = register(&s, 1, 10);
+    let donors = [3i128, 3, 4].map(|amount| {
+        let donor = Address::generate(&s.env);
+        s.token_admin.mint(&donor, &amount);
+        s.contract.fund_mission(&id, &donor, &amount);
+        donor
+    });
+    s.contract.withdraw(&id);
+    // Put part of the payout back so the pool does not divide evenly.
+    s.token_admin.mint(&s.contract.address, &5);
+    s.env.as_contract(&s.contract.address, || {
+        let mut mission = load_mission(&s.env, &id).unwrap();
+        mission.withdrawn = 5;
+        mission.funds.set(s.token.address.clone(), AssetFunds { raised: 10, held: 5 });
+        save_mission(&s.env, &id, &mission);
+    });
+
+    assert_eq!(s.contract.cancel_mission(&id), 5);
+    let refunds = donors.map(|donor| s.contract.claim_refund(&id, &donor));
+    assert_eq!(refunds, [1, 1, 3]);
+    assert_eq!(s.token.balance(&s.contract.address), 0);
+    assert_eq!(s.contract.try_withdraw(&id), Err(Ok(Error::MissionCancelled)));
+}
+
+/// Registers another Stellar Asset Contract and whitelists it at `rate`
+/// platform-token units per unit.
+fn extra_asset<'a>(s: &Setup, rate: i128) -> (token::Client<'a>, token::StellarAssetClient<'a>) {
+    let sac = s.env.register_stellar_asset_contract_v2(s.admin.clone());
+    s.contract
+        .set_asset_rate(&sac.address(), &Some(rate * asset::RATE_SCALE));
+    (
+        token::Client::new(&s.env, &sac.address()),
+        token::StellarAssetClient::new(&s.env, &sac.address()),
+    )
+}
+
+#[test]
+fn donations_in_several_assets_count_towards_the_reference_target() {
+    let s = setup();
+    let (usdc, usdc_admin) = extra_asset(&s, 2);
+    let id = register(&s, 1, 1_000);
+    s.contract.set_mission_assets(
+        &id,
+        &usdc.address,
+        &vec![&s.env, s.token.address.clone(), usdc.address.clone()],
+    );
+    let alice = Address::generate(&s.env);
+    let bob = Address::generate(&s.env);
+    usdc_admin.mint(&alice, &400);
+    s.token_admin.mint(&alice, &500);
+    s.token_admin.mint(&bob, &700);
+
+    s.contract.fund_mission(&id, &alice, &400);
+    s.contract.fund_mission_with(&id, &alice, &s.token.address, &500);
+    assert_eq!(
+        contract_events(&s).slice(0..1),
+        vec![
+            &s.env,
+            (
+                s.contract.address.clone(),
+                (symbol_short!("mission"), symbol_short!("fund_asst"), id.clone()).into_val(&s.env),
+                (alice.clone(), s.token.address.clone(), 500i128).into_val(&s.env),
+            ),
+        ]
+    );
+    assert_eq!(s.contract.get_mission(&id).funded_amount, 650);
+    assert_eq!(s.contract.get_remaining_funds(&id), 350);
+
+    s.contract.fund_mission_with(&id, &bob, &s.token.address, &700);
+    assert_eq!(s.contract.get_mission_status(&id), MissionStatus::Funded);
+    let mission = s.contract.get_mission(&id);
+    assert_eq!(
+        mission.funds.get(s.token.address.clone()),
+        Some(AssetFunds { raised: 1_200, held: 1_200 })
+    );
+    assert_eq!(
+        mission.funds.get(usdc.address.clone()),
+        Some(AssetFunds { raised: 400, held: 400 })
+    );
+    let alice_assets = s.contract.get_contribution_assets(&id, &alice);
+    assert_eq!(alice_assets.get(usdc.address.clone()), Some(400));
+    assert_eq!(alice_assets.get(s.token.address.clone()), Some(500));
+    assert_eq!(s.contract.get_contribution(&id, &alice).amount, 650);
+
+    assert_eq!(s.contract.withdraw(&id), 1_000);
+    assert_eq!(usdc.balance(&s.owner), 400);
+    assert_eq!(s.token.balance(&s.owner), 1_200);
+    assert_eq!(s.token.balance(&s.contract.address), 0);
+}
+
+#[test]
+fn donations_worth_nothing_in_the_reference_asset_are_rejected() {
+    let s = setup();
+    let (usdc, _) = extra_asset(&s, 2);
+    let id = register(&s, 1, 1_000);
+    s.contract.set_mission_assets(
+        &id,
+        &usdc.address,
+        &vec![&s.env, s.token.address.clone(), usdc.address.clone()],
+    );
+    let donor = Address::generate(&s.env);
+    s.token_admin.mint(&donor, &2);
+
+    // One platform token is half a unit of the reference asset.
+    assert_eq!(
+        s.contract.try_fund_mission_with(&id, &donor, &s.token.address, &1),
+        Err(Ok(Error::InvalidAmount))
+    );
+    assert_eq!(s.token.balance(&donor), 2);
+
+    s.contract.fund_mission_with(&id, &donor, &s.token.address, &2);
+    assert_eq!(s.contract.get_contribution(&id, &donor).amount, 1);
+    s.env.ledger().set_timestamp(DEADLINE + 1);
+    assert_eq!(s.contract.claim_refund(&id, &donor), 1);
+    assert_eq!(s.token.balance(&donor), 2);
+}
+
+#[test]
+fn multi_asset_refunds_are_paid_in_kind() {
+    let s = setup();
+    let (usdc, usdc_admin) = extra_asset(&s, 3);
+    let id = register(&s, 1, 1_000);
+    s.contract.set_mission_assets(
+        &id,
+        &s.token.address,
+        &vec![&s.env, s.token.address.clone(), usdc.address.clone()],
+    );
+    let donor = Address::generate(&s.env);
+    usdc_admin.mint(&donor, &100);
+    s.token_admin.mint(&donor, &50);
+    s.contract.fund_mission_with(&id, &donor, &usdc.address, &100);
+    s.contract.fund_mission(&id, &donor, &50);
+    assert_eq!(s.contract.get_mission(&id).funded_amount, 350);
+
+    // Delisting an asset stops new donations in it, not refunds.
+    s.contract.set_asset_rate(&usdc.address, &None);
+    assert_eq!(
+        s.contract.try_fund_mission_with(&id, &donor, &usdc.address, &1),
+        Err(Ok(Error::UnsupportedAsset))
+    );
+
+    s.env.ledger().set_timestamp(DEADLINE + 1);
+    assert_eq!(s.contract.claim_refund(&id, &donor), 350);
+    assert_eq!(usdc.balance(&donor), 100);
+    assert_eq!(s.token.balance(&donor), 50);
+    assert!(s.contract.get_contribution_assets(&id, &donor).is_empty());
+}
+
+#[test]
+fn mission_assets_are_validated_and_locked_by_donations() {
+    let s = setup();
+    let (usdc, _) = extra_asset(&s, 1);
+    let eurc = s.env.register_stellar_asset_contract_v2(s.admin.clone()).address();
+    let id = register(&s, 1, 1_000);
+    let platform = s.token.address.clone();
+
+    assert_eq!(
+        s.contract.try_set_asset_rate(&platform, &Some(1)),
+        Err(Ok(Error::InvalidConfig))
+    );
+    assert_eq!(
+        s.contract.try_set_asset_rate(&eurc, &Some(0)),
+        Err(Ok(Error::InvalidConfig))
+    );
+    assert_eq!(
+        s.contract
+            .try_set_mission_assets(&id, &eurc, &vec![&s.env, eurc.clone()]),
+        Err(Ok(Error::UnsupportedAsset))
+    );
+    assert_eq!(
+        s.contract
+            .try_set_mission_assets(&id, &usdc.address, &vec![&s.env, platform.clone()]),
+        Err(Ok(Error::InvalidConfig))
+    );
+    assert_eq!(
+        s.contract.try_set_mission_assets(
+            &id,
+            &platform,
+            &vec![&s.env, platform.clone(), platform.clone()]
+        ),
+        Err(Ok(Error::InvalidConfig))
+    );
+
+    let donor = Address::generate(&s.env);
+    s.token_admin.mint(&donor, &10);
+    assert_eq!(
+        s.contract.try_fund_mission_with(&id, &donor, &usdc.address, &10),
+        Err(Ok(Error::UnsupportedAsset))
+    );
+    s.contract.fund_mission(&id, &donor, &10);
+    assert_eq!(
+        s.contract
+            .try_set_mission_assets(&id, &usdc.address, &vec![&s.env, usdc.address.clone()]),
+        Err(Ok(Error::AssetsLocked))
+    );
+}
+
+fn rarity_of(s: &Setup, token_id: u64) -> String {
+    let attribute = nft_attribute(&s.contract.token_metadata(&token_id), 3);
+    let trait_type: String = attribute
+        .get(Symbol::new(&s.env, "trait_type"))
+        .unwrap()
+        .into_val(&s.env);
+    assert_eq!(trait_type, String::from_str(&s.env, "Rarity"));
+    attribute
+        .get(Symbol::new(&s.env, "value"))
+        .unwrap()
+        .into_val(&s.env)
+}
+
+#[test]
+fn nft_rarity_follows_the_donors_share_of_the_target() {
+    let s = setup();
+    let id = register(&s, 1, 1_000);
+    let alice = Address::generate(&s.env);
+    let bob = Address::generate(&s.env);
+    s.token_admin.mint(&alice, &1_000);
+    s.token_admin.mint(&bob, &1_000);
+    let tier = |name| String::from_str(&s.env, name);
+
+    let first = s.contract.fund_mission(&id, &alice, &19);
+    assert_eq!(rarity_of(&s, first), tier("Common"));
+    let whale = s.contract.fund_mission(&id, &bob, &160);
+    assert_eq!(rarity_of(&s, whale), tier("Legendary"));
+
+    // Topping up to 2% lifts the earlier token along with the new one.
+    let second = s.contract.fund_mission(&id, &alice, &1);
+    assert!(contract_events(&s).contains((
+        s.contract.address.clone(),
+        (symbol_short!("nft"), symbol_short!("retier"), id.clone()).into_val(&s.env),
+        (alice.clone(), Rarity::Rare).into_val(&s.env),
+    )));
+    assert_eq!(rarity_of(&s, first), tier("Rare"));
+    assert_eq!(rarity_of(&s, second), tier("Rare"));
+    assert_eq!(s.contract.token_metadata(&first).len(), 4);
+
+    let third = s.contract.fund_mission(&id, &alice, &40);
+    for token_id in [first, second, third] {
+        assert_eq!(rarity_of(&s, token_id), tier("Epic"));
+    }
+    assert_eq!(rarity_of(&s, whale), tier("Legendary"));
+
+    // The tier stays with the donor, not whoever holds the token.
+    s.contract.transfer(&alice, &bob, &first);
+    assert_eq!(rarity_of(&s, first), tier("Epic"));
+}
+
+#[test]
+fn legacy_missions_without_a_target_migrate_as_common() {
+    let s = setup();
+    let id = BytesN::from_array(&s.env, &[7; 32]);
+    let donor = Address::generate(&s.env);
+    let legacy = LegacyMission {
+        title: String::from_str(&s.env, "Legacy"),
+        desc: String::from_str(&s.env, "Pre-DataKey mission"),
+        image_url: String::from_str(&s.env, "ipfs://legacy.png"),
+        target_amount: 0,
+        funded_amount: 40,
+        owner: s.owner.clone(),
+    };
+    s.env.as_contract(&s.contract.address, || {
+        let mut meta: Map<Symbol, Val> = Map::new(&s.env);
+        meta.set(symbol_short!("owner"), donor.into_val(&s.env));
+        meta.set(symbol_short!("contrib"), 40_i128.into_val(&s.env));
+        let nft_key = s.env.crypto().sha256(&id.clone().into()).to_bytes();
+        s.env.storage().persistent().set(&id, &legacy);
+        s.env.storage().persistent().set(&nft_key, &meta);
+    });
+
+    assert_eq!(s.contract.migrate_legacy_missions(&vec![&s.env, id], &DEADLINE), 1);
+    assert_eq!(rarity_of(&s, 0), String::from_str(&s.env, "Common"));
+}
+
+#[test]
+fn rarity_thresholds_are_admin_configurable() {
+    let s = setup();
+    assert_eq!(
+        s.contract.get_rarity_thresholds(),
+        RarityThresholds { rare: 2, epic: 6, legendary: 16 }
+    );
+    for bad in [(0, 6, 16), (6, 6, 16), (2, 20, 16)] {
+        let thresholds = RarityThresholds { rare: bad.0, epic: bad.1, legendary: bad.2 };
+        assert_eq!(
+            s.contract.try_set_rarity_thresholds(&thresholds),
+            Err(Ok(Error::InvalidConfig))
+        );
+    }
+    let thresholds = RarityThresholds { rare: 10, epic: 25, legendary: 50 };
+    s.contract.set_rarity_thresholds(&thresholds);
+    assert_eq!(s.contract.get_rarity_thresholds(), thresholds);
+
+    let id = register(&s, 1, 100);
+    let donor = Address::generate(&s.env);
+    s.token_admin.mint(&donor, &100);
+    let token_id = s.contract.fund_mission(&id, &donor, &24);
+    assert_eq!(rarity_of(&s, token_id), String::from_str(&s.env, "Rare"));
+}
+
+/// Per-transaction resource limits of the Stellar network. Native test
+/// contracts are not metered for wasm execution, so calls are held to a
+/// quarter of the CPU and memory limits; ledger entry counts are exact and
+/// held to the limits themselves.
+const TX_CPU_LIMIT: u64 = 100_000_000;
+const TX_MEM_LIMIT: u64 = 40 * 1024 * 1024;
+const TX_READ_ENTRIES_LIMIT: u32 = 40;
+const TX_WRITE_ENTRIES_LIMIT: u32 = 25;
+
+/// Checks the resources metered for the last contract call.
+fn assert_within_budget(s: &Setup, call: &str) {
+    let budget = s.env.cost_estimate().budget();
+    let cpu = budget.cpu_instruction_cost();
+    let mem = budget.memory_bytes_cost();
+    assert!(cpu <= TX_CPU_LIMIT / 4, "{call} used {cpu} CPU instructions");
+    assert!(mem <= TX_MEM_LIMIT / 4, "{call} used {mem} bytes of memory");
+    let resources = s.env.cost_estimate().resources();
+    let (reads, writes) = (resources.read_entries, resources.write_entries);
+    assert!(reads <= TX_READ_ENTRIES_LIMIT, "{call} read {reads} ledger entries");
+    assert!(writes <= TX_WRITE_ENTRIES_LIMIT, "{call} wrote {writes} ledger entries");
+}
+
+#[test]
+fn mission_lifecycle_calls_fit_the_network_budget() {
+    let s = setup();
+    s.contract.set_role(&Role::FeeCollector, &Some(Address::generate(&s.env)));
+    s.contract.set_fee_bps(&250);
+    let (usdc, usdc_admin) = extra_asset(&s, 2);
+    let reviewer = Address::generate(&s.env);
+    let plan = vec![&s.env, milestone(&s, "Design", 50), milestone(&s, "Launch", 50)];
+    let id = register_with_milestones(&s, 1, 1_000, plan, Some(reviewer));
+    assert_within_budget(&s, "register_mission");
+    s.contract.set_mission_assets(
+        &id,
+        &s.token.address,
+        &vec![&s.env, s.token.address.clone(), usdc.address.clone()],
+    );
+    assert_within_budget(&s, "set_mission_assets");
+
+    let donor = Address::generate(&s.env);
+    s.token_admin.mint(&donor, &2_000);
+    usdc_admin.mint(&donor, &2_000);
+    s.contract.fund_mission(&id, &donor, &100);
+    assert_within_budget(&s, "fund_mission");
+    s.contract.fund_mission_with(&id, &donor, &usdc.address, &600);
+    assert_within_budget(&s, "fund_mission_with");
+
+    let evidence = BytesN::from_array(&s.env, &[0xab; 32]);
+    s.contract.submit_milestone(&id, &0, &evidence);
+    assert_within_budget(&s, "submit_milestone");
+    s.contract.review_milestone(&id, &0, &true);
+    assert_within_budget(&s, "review_milestone");
+    s.contract.cancel_mission(&id);
+    assert_within_budget(&s, "cancel_mission");
+    s.contract.claim_refund(&id, &donor);
+    assert_within_budget(&s, "claim_refund");
+    s.contract.token_uri(&0);
+    assert_within_budget(&s, "token_uri");
+}
+
+#[test]
+fn full_pages_and_batches_fit_the_network_budget() {
+    let s = setup();
+    let registered = index::MAX_PAGE_SIZE.max(upgrade::MAX_MIGRATION_BATCH) as u8;
+    let ids: std::vec::Vec<_> = (0..registered).map(|seed| register(&s, seed, 1_000)).collect();
+
+    assert_eq!(s.contract.list_missions(&0, &u32::MAX).len(), index::MAX_PAGE_SIZE);
+    assert_within_budget(&s, "list_missions");
+    s.contract
+        .missions_by_status(&MissionStatus::Funded, &0, &u32::MAX);
+    assert_within_budget(&s, "missions_by_status");
+
+    let batch = &ids[..upgrade::MAX_MIGRATION_BATCH as usize];
+    downgrade_to_v1(&s, batch, 0);
+    s.contract.migrate(&u32::MAX);
+    assert_within_budget(&s, "migrate");
+}
+
+#[test]
+fn full_bump_pages_fit_the_network_budget() {
+    let s = setup();
+    let id = register(&s, 1, 100_000);
+    for _ in 0..contribution::MAX_BUMP_DONORS + 1 {
+        let donor = donate(&s, &id, 10);
+        s.token_admin.mint(&donor, &100);
+        s.contract
+            .create_pledge(&id, &donor, &s.token.address, &10, &100, &200);
+    }
+    let signer = Address::generate(&s.env);
+    s.contract.set_team(&id, &vec![&s.env, signer.clone()], &1, &s.owner);
+    for _ in 0..index::MAX_PAGE_SIZE + 1 {
+        s.contract.propose(&id, &signer, &TeamAction::Close);
+    }
+
+    s.contract.bump_mission(&id);
+    assert_within_budget(&s, "bump_mission");
+    let bumped = s.contract.bump_donors(&id, &0, &u32::MAX);
+    assert_eq!(bumped, contribution::MAX_BUMP_DONORS);
+    assert_within_budget(&s, "bump_donors");
+    assert_eq!(s.contract.bump_donors(&id, &bumped, &u32::MAX), 1);
+    let bumped = s.contract.bump_proposals(&id, &0, &u32::MAX);
+    assert_eq!(bumped, index::MAX_PAGE_SIZE);
+    assert_within_budget(&s, "bump_proposals");
+}
+
+#[test]
+fn retiering_writes_nothing_extra_however_long_the_history() {
+    let s = setup();
+    let id = register(&s, 1, 100_000);
+    let donor = Address::generate(&s.env);
+    s.token_admin.mint(&donor, &10_000);
+    for _ in 0..50 {
+        s.contract.fund_mission(&id, &donor, &1);
+    }
+    let same_tier = s.env.cost_estimate().resources().write_entries;
+
+    // 50 + 1_950 is 2% of the target: every earlier token turns Rare, yet
+    // the donation writes no more than one that stays in its tier.
+    let token_id = s.contract.fund_mission(&id, &donor, &1_950);
+    assert_eq!(s.env.cost_estimate().resources().write_entries, same_tier);
+    assert_within_budget(&s, "fund_mission re-tiering 50 tokens");
+    assert_eq!(rarity_of(&s, 0), String::from_str(&s.env, "Rare"));
+    assert_eq!(rarity_of(&s, token_id), String::from_str(&s.env, "Rare"));
+}
+
+#[test]
+fn donations_that_would_overflow_totals_are_rejected() {
+    let s = setup();
+    let id = register(&s, 1, 1_000);
+    let whale = Address::generate(&s.env);
+    let other = Address::generate(&s.env);
+    let half = i128::MAX / 2 + 1;
+    s.token_admin.mint(&whale, &half);
+    s.token_admin.mint(&other, &half);
+    s.contract.fund_mission(&id, &whale, &half);
+    assert_eq!(rarity_of(&s, 0), String::from_str(&s.env, "Legendary"));
+
+    assert_eq!(
+        s.contract.try_fund_mission(&id, &other, &half),
+        Err(Ok(Error::Overflow))
+    );
+    assert_eq!(s.contract.get_mission(&id).funded_amount, half);
+    assert_eq!(s.token.balance(&other), half);
+
+    // Valuing the donation in the reference asset overflows too.
+    let (usdc, usdc_admin) = extra_asset(&s, 2);
+    let id = register(&s, 2, 1_000);
+    s.contract.set_mission_assets(
+        &id,
+        &s.token.address,
+        &vec![&s.env, s.token.address.clone(), usdc.address.clone()],
+    );
+    usdc_admin.mint(&other, &half);
+    assert_eq!(
+        s.contract.try_fund_mission_with(&id, &other, &usdc.address, &half),
+        Err(Ok(Error::Overflow))
+    );
+    assert_eq!(usdc.balance(&other), half);
+}
+
+/// A mission handed to a team of three; its payout address is the owner's.
+fn team_mission(s: &Setup, seed: u8, threshold: u32) -> (BytesN<32>, [Address; 3]) {
+    let id = register(s, seed, 1_000);
+    let signers = [
+        Address::generate(&s.env),
+        Address::generate(&s.env),
+        Address::generate(&s.env),
+    ];
+    s.contract.set_team(
+        &id,
+        &Vec::from_array(&s.env, signers.clone()),
+        &threshold,
+        &s.owner,
+    );
+    (id, signers)
+}
+
+#[test]
+fn team_withdrawal_needs_threshold_approvals() {
+    let s = setup();
+    let (id, [alice, bob, carol]) = team_mission(&s, 1, 2);
+    let donor = Address::generate(&s.env);
+    s.token_admin.mint(&donor, &1_000);
+    s.contract.fund_mission(&id, &donor, &1_000);
+
+    assert_eq!(s.contract.try_withdraw(&id), Err(Ok(Error::TeamApprovalRequired)));
+    assert_eq!(
+        s.contract.try_set_team(&id, &vec![&s.env, s.owner.clone()], &1, &s.owner),
+        Err(Ok(Error::TeamApprovalRequired))
+    );
+
+    let treasury = Address::generate(&s.env);
+    let stranger = Address::generate(&s.env);
+    assert_eq!(
+        s.contract
+            .try_propose(&id, &stranger, &TeamAction::Withdraw(stranger.clone())),
+        Err(Ok(Error::NotTeamMember))
+    );
+    let proposal = s
+        .contract
+        .propose(&id, &alice, &TeamAction::Withdraw(treasury.clone()));
+    assert_eq!(
+        s.contract.try_execute_proposal(&id, &proposal),
+        Err(Ok(Error::NotApproved))
+    );
+    assert_eq!(
+        s.contract.try_approve_proposal(&id, &proposal, &alice),
+        Err(Ok(Error::AlreadyExists))
+    );
+
+    // Approving takes the signer's own signature.
+    sign_as(
+        &s,
+        &carol,
+        "approve_proposal",
+        (id.clone(), proposal, bob.clone()).into_val(&s.env),
+    );
+    assert!(s.contract.try_approve_proposal(&id, &proposal, &bob).is_err());
+    s.env.mock_all_auths();
+    assert_eq!(s.contract.approve_proposal(&id, &proposal, &bob), 2);
+
+    assert_eq!(s.contract.execute_proposal(&id, &proposal), 1_000);
+    assert_eq!(s.token.balance(&treasury), 1_000);
+    assert!(s.contract.get_proposal(&id, &proposal).executed);
+    assert_eq!(
+        s.contract.try_execute_proposal(&id, &proposal),
+        Err(Ok(Error::ProposalClosed))
+    );
+    assert_eq!(
+        s.contract.try_approve_proposal(&id, &proposal, &carol),
+        Err(Ok(Error::ProposalClosed))
+    );
+    assert_eq!(
+        s.contract.try_get_proposal(&id, &9),
+        Err(Ok(Error::ProposalNotFound))
+    );
+}
+
+#[test]
+fn team_rotation_needs_approval_and_voids_open_proposals() {
+    let s = setup();
+    let (id, [alice, bob, carol]) = team_mission(&s, 1, 2);
+    let dave = Address::generate(&s.env);
+    assert_eq!(
+        s.contract.try_propose(
+            &id,
+            &alice,
+            &TeamAction::SetTeam(vec![&s.env, dave.clone()], 2, dave.clone())
+        ),
+        Err(Ok(Error::InvalidConfig))
+    );
+    assert_eq!(
+        s.contract.try_propose(
+            &id,
+            &alice,
+            &TeamAction::SetTeam(vec![&s.env, dave.clone(), dave.clone()], 1, dave.clone())
+        ),
+        Err(Ok(Error::InvalidConfig))
+    );
+
+    let pending = s
+        .contract
+        .propose(&id, &carol, &TeamAction::Withdraw(carol.clone()));
+    let rotation = s.contract.propose(
+        &id,
+        &alice,
+        &TeamAction::SetTeam(vec![&s.env, alice.clone(), dave.clone()], 2, dave.clone()),
+    );
+    s.contract.approve_proposal(&id, &rotation, &bob);
+    s.contract.execute_proposal(&id, &rotation);
+    let team = s.contract.get_team(&id).unwrap();
+    assert_eq!(team.signers, vec![&s.env, alice.clone(), dave.clone()]);
+    assert_eq!(team.payout, dave);
+    assert_eq!(team.epoch, 1);
+
+    // Proposals and signers of the old team no longer count.
+    assert_eq!(
+        s.contract.try_approve_proposal(&id, &pending, &alice),
+        Err(Ok(Error::ProposalClosed))
+    );
+    assert_eq!(
+        s.contract
+            .try_propose(&id, &carol, &TeamAction::Withdraw(carol.clone())),
+        Err(Ok(Error::NotTeamMember))
+    );
+}
+
+#[test]
+fn team_submits_milestones_through_proposals() {
+    let s = setup();
+    let reviewer = Address::generate(&s.env);
+    let plan = vec![&s.env, milestone(&s, "Design", 50), milestone(&s, "Launch", 50)];
+    let id = register_with_milestones(&s, 1, 1_000, plan, Some(reviewer));
+    let alice = Address::generate(&s.env);
+    let treasury = Address::generate(&s.env);
+    s.contract.set_team(&id, &vec![&s.env, alice.clone()], &1, &treasury);
+    let donor = Address::generate(&s.env);
+    s.token_admin.mint(&donor, &1_000);
+    s.contract.fund_mission(&id, &donor, &1_000);
+
+    let evidence = BytesN::from_array(&s.env, &[0xab; 32]);
+    assert_eq!(
+        s.contract.try_submit_milestone(&id, &0, &evidence),
+        Err(Ok(Error::TeamApprovalRequired))
+    );
+    let proposal = s.contract.propose(
+        &id,
+        &alice,
+        &TeamAction::SubmitMilestone(0, evidence.clone()),
+    );
+    assert_eq!(s.contract.execute_proposal(&id, &proposal), 0);
+    let submitted = s.contract.get_milestone_plan(&id).milestones.get(0).unwrap();
+    assert_eq!(submitted.status, MilestoneStatus::UnderReview);
+    assert_eq!(submitted.evidence, vec![&s.env, evidence]);
+
+    // Releases go to the team's payout address, not the owner.
+    s.contract.review_milestone(&id, &0, &true);
+    assert_eq!(s.token.balance(&treasury), 500);
+    assert_eq!(s.token.balance(&s.owner), 0);
+}
+
+#[test]
+fn owner_alone_cannot_act_for_a_team_mission() {
+    let s = setup();
+    let (id, [alice, bob, _]) = team_mission(&s, 1, 2);
+    let title = String::from_str(&s.env, "Renamed");
+    let callback = TargetCallback {
+        contract: Address::generate(&s.env),
+        function: Symbol::new(&s.env, "on_target"),
+    };
+    let (round, _) = open_round(&s, 100, 0, 5_000);
+    let solo = Err(Ok(Error::TeamApprovalRequired));
+    assert_eq!(s.contract.try_update_mission_metadata(&id, &title, &title, &title), solo);
+    assert_eq!(s.contract.try_close_mission(&id), solo);
+    assert_eq!(
+        s.contract.try_cancel_mission(&id),
+        Err(Ok(Error::TeamApprovalRequired))
+    );
+    assert_eq!(
+        s.contract.try_set_mission_assets(
+            &id,
+            &s.token.address,
+            &vec![&s.env, s.token.address.clone()]
+        ),
+        solo
+    );
+    assert_eq!(
+        s.contract.try_set_target_callback(&id, &Some(callback.clone())),
+        solo
+    );
+    assert_eq!(s.contract.try_join_round(&round, &id), solo);
+
+    let approved = |action: TeamAction| {
+        let proposal = s.contract.propose(&id, &alice, &action);
+        s.contract.approve_proposal(&id, &proposal, &bob);
+        s.contract.execute_proposal(&id, &proposal)
+    };
+    approved(TeamAction::UpdateMetadata(title.clone(), title.clone(), title.clone()));
+    assert_eq!(s.contract.get_mission(&id).title, title);
+    approved(TeamAction::SetAssets(
+        s.token.address.clone(),
+        vec![&s.env, s.token.address.clone()],
+    ));
+    approved(TeamAction::SetTargetCallback(callback.clone()));
+    assert_eq!(s.contract.get_target_callback(&id), Some(callback));
+    approved(TeamAction::ClearTargetCallback);
+    assert_eq!(s.contract.get_target_callback(&id), None);
+    approved(TeamAction::JoinRound(round));
+    assert_eq!(s.contract.get_round(&round).missions, vec![&s.env, id.clone()]);
+    approved(TeamAction::Close);
+    assert_eq!(s.contract.get_mission_status(&id), MissionStatus::Closed);
+    approved(TeamAction::Cancel);
+    assert_eq!(s.contract.get_mission_status(&id), MissionStatus::Cancelled);
+}
+
+#[test]
+fn team_matches_go_to_the_payout_address() {
+    let s = setup();
+    let (round, _) = open_round(&s, 1_000, 0, 5_000);
+    let id = register(&s, 1, 200);
+    let treasury = Address::generate(&s.env);
+    s.contract.join_round(&round, &id);
+    s.contract
+        .set_team(&id, &vec![&s.env, Address::generate(&s.env)], &1, &treasury);
+    donate(&s, &id, 100);
+    donate(&s, &id, 100);
+    s.env.ledger().with_mut(|li| li.timestamp = 5_001);
+    s.contract.finalize_round(&round);
+
+    assert_eq!(s.contract.claim_match(&round, &id), 1_000);
+    assert_eq!(s.token.balance(&treasury), 1_000);
+    assert_eq!(s.token.balance(&s.owner), 0);
+}
+
+/// Stand-in for a compliance partner's contract.
+#[contract]
+struct MockAttestor;
+
+#[contractimpl]
+impl MockAttestor {
+    pub fn set_verified(env: Env, donor: Address, verified: bool) {
+        env.storage().persistent().set(&donor, &verified);
+    }
+
+    pub fn is_verified(env: Env, donor: Address) -> bool {
+        env.storage().persistent().get(&donor).unwrap_or(false)
+    }
+}
+
+fn register_attested(s: &Setup, seed: u8, attestor: &Address) -> BytesN<32> {
+    let id = BytesN::from_array(&s.env, &[seed; 32]);
+    let title = String::from_str(&s.env, "Gene therapy trial");
+    s.contract.register_mission(
+        &id,
+        &title,
+        &title,
+        &title,
+        &1_000,
+        &DEADLINE,
+        &s.owner,
+        &vec![&s.env],
+        &None,
+        &Some(attestor.clone()),
+    );
+    id
+}
+
+#[test]
+fn attested_mission_only_accepts_verified_donors() {
+    let s = setup();
+    let attestor_id = s.env.register(MockAttestor, ());
+    let attestor = MockAttestorClient::new(&s.env, &attestor_id);
+    let id = register_attested(&s, 1, &attestor_id);
+    let open = register(&s, 2, 1_000);
+    assert_eq!(s.contract.get_mission_attestor(&id), Some(attestor_id.clone()));
+    assert_eq!(s.contract.get_mission_attestor(&open), None);
+
+    let donor = Address::generate(&s.env);
+    s.token_admin.mint(&donor, &500);
+    assert_eq!(
+        s.contract.try_fund_mission(&id, &donor, &100),
+        Err(Ok(Error::DonorNotVerified))
+    );
+    assert_eq!(
+        s.contract
+            .try_create_pledge(&id, &donor, &s.token.address, &100, &1_000, &200),
+        Err(Ok(Error::DonorNotVerified))
+    );
+    // Missions without an attestor are unaffected.
+    s.contract.fund_mission(&open, &donor, &100);
+
+    attestor.set_verified(&donor, &true);
+    s.contract.fund_mission(&id, &donor, &100);
+    assert_eq!(s.contract.get_mission(&id).funded_amount, 100);
+
+    // Verification is checked on every donation, pledged ones included.
+    s.token.approve(&donor, &s.contract.address, &200, &1_000);
+    s.contract
+        .create_pledge(&id, &donor, &s.token.address, &100, &1_000, &200);
+    attestor.set_verified(&donor, &false);
+    assert_eq!(
+        s.contract.try_collect_pledge(&id, &donor),
+        Err(Ok(Error::DonorNotVerified))
+    );
+    assert_eq!(s.token.balance(&donor), 300);
+}
+
+#[test]
+fn failing_attestor_counts_as_unverified() {
+    let s = setup();
+    // An address with no contract behind it cannot vouch for anyone.
+    let id = register_attested(&s, 1, &Address::generate(&s.env));
+    let donor = Address::generate(&s.env);
+    s.token_admin.mint(&donor, &500);
+    assert_eq!(
+        s.contract.try_fund_mission(&id, &donor, &100),
+        Err(Ok(Error::DonorNotVerified))
+    );
+}
+
+/// Partner contract recording target notifications. With a `reenter`
+/// target set it instead tries to donate again from inside the callback;
+/// with `fail` set it panics.
+#[contract]
+struct MockPartner;
+
+#[contractimpl]
+impl MockPartner {
+    pub fn reenter(env: Env, fund: Address, donor: Address) {
+        env.storage().instance().set(&symbol_short!("reenter"), &(fund, donor));
+    }
+
+    pub fn fail(env: Env) {
+        env.storage().instance().set(&symbol_short!("fail"), &true);
+    }
+
+    pub fn on_target(env: Env, mission_id: BytesN<32>, target: i128, funded: i128) {
+        if env.storage().instance().has(&symbol_short!("fail")) {
+            panic!("procurement unavailable");
+        }
+        if let Some((fund, donor)) = env
+            .storage()
+            .instance()
+            .get::<_, (Address, Address)>(&symbol_short!("reenter"))
+        {
+            MissionFundContractClient::new(&env, &fund).fund_mission(&mission_id, &donor, &1);
+        }
+        env.storage()
+            .instance()
+            .set(&symbol_short!("notified"), &(mission_id, target, funded));
+    }
+
+    pub fn notified(env: Env) -> Option<(BytesN<32>, i128, i128)> {
+        env.storage().instance().get(&symbol_short!("notified"))
+    }
+}
+
+fn partner<'a>(s: &Setup, id: &BytesN<32>) -> MockPartnerClient<'a> {
+    let partner = MockPartnerClient::new(&s.env, &s.env.register(MockPartner, ()));
+    s.contract.set_target_callback(
+        id,
+        &Some(TargetCallback {
+            contract: partner.address.clone(),
+            function: Symbol::new(&s.env, "on_target"),
+        }),
+    );
+    partner
+}
+
+#[test]
+fn target_callback_fires_once_when_target_is_reached() {
+    let s = setup();
+    let id = register(&s, 1, 1_000);
+    let partner = partner(&s, &id);
+    assert_eq!(
+        s.contract.get_target_callback(&id).unwrap().contract,
+        partner.address
+    );
+    let donor = Address::generate(&s.env);
+    s.token_admin.mint(&donor, &2_000);
+
+    s.contract.fund_mission(&id, &donor, &600);
+    assert!(!s.contract.pending_callback(&id));
+    assert_eq!(
+        s.contract.try_notify_target(&id),
+        Err(Ok(Error::NoPendingCallback))
+    );
+
+    // The donation that reaches the target calls the partner.
+    s.contract.fund_mission(&id, &donor, &500);
+    assert!(contract_events(&s).contains((
+        s.contract.address.clone(),
+        (symbol_short!("mission"), symbol_short!("callback"), id.clone()).into_val(&s.env),
+        (partner.address.clone(), true).into_val(&s.env),
+    )));
+    assert_eq!(partner.notified(), Some((id.clone(), 1_000, 1_100)));
+    assert!(!s.contract.pending_callback(&id));
+    assert_eq!(
+        s.contract.try_notify_target(&id),
+        Err(Ok(Error::NoPendingCallback))
+    );
+
+    // Later donations do not call it again.
+    s.contract.fund_mission(&id, &donor, &100);
+    let callback: Val = symbol_short!("callback").into_val(&s.env);
+    assert!(!contract_events(&s)
+        .iter()
+        .any(|event| event.1.contains(callback)));
+    assert_eq!(partner.notified(), Some((id.clone(), 1_000, 1_100)));
+
+    sign_as(&s, &donor, "set_target_callback", (id.clone(), None::<TargetCallback>).into_val(&s.env));
+    assert!(s.contract.try_set_target_callback(&id, &None).is_err());
+    s.env.mock_all_auths();
+    s.contract.set_target_callback(&id, &None);
+    assert_eq!(s.contract.get_target_callback(&id), None);
+}
+
+#[test]
+fn failing_or_reentrant_callback_does_not_undo_the_donation() {
+    let s = setup();
+    let donor = Address::generate(&s.env);
+    s.token_admin.mint(&donor, &3_000);
+
+    let failing = register(&s, 1, 1_000);
+    let failing_partner = partner(&s, &failing);
+    failing_partner.fail();
+    s.contract.fund_mission(&failing, &donor, &1_000);
+    assert!(contract_events(&s).contains((
+        s.contract.address.clone(),
+        (symbol_short!("mission"), symbol_short!("cb_due"), failing.clone()).into_val(&s.env),
+        (failing_partner.address.clone(), 1_000_i128).into_val(&s.env),
+    )));
+    assert!(!s.contract.notify_target(&failing));
+    assert!(contract_events(&s).contains((
+        s.contract.address.clone(),
+        (symbol_short!("mission"), symbol_short!("callback"), failing.clone()).into_val(&s.env),
+        (failing_partner.address.clone(), false).into_val(&s.env),
+    )));
+    assert_eq!(s.contract.get_mission(&failing).funded_amount, 1_000);
+
+    // A failed call stays pending until it goes through or is removed.
+    assert!(s.contract.pending_callback(&failing));
+    s.contract.set_target_callback(&failing, &None);
+    assert!(!s.contract.pending_callback(&failing));
+
+    // The host refuses to let the partner re-enter the contract.
+    let reentrant = register(&s, 2, 1_000);
+    let partner = partner(&s, &reentrant);
+    partner.reenter(&s.contract.address, &donor);
+    s.contract.fund_mission(&reentrant, &donor, &1_000);
+    assert!(s.contract.pending_callback(&reentrant));
+    assert!(!s.contract.notify_target(&reentrant));
+    assert_eq!(partner.notified(), None);
+    assert_eq!(s.contract.get_mission(&reentrant).funded_amount, 1_000);
+    assert_eq!(s.contract.get_contribution(&reentrant, &donor).count, 1);
+    assert_eq!(s.token.balance(&donor), 1_000);
+}
+
+fn basket(s: &Setup, first_seed: u8, amounts: &[i128]) -> Vec<(BytesN<32>, i128)> {
+    let mut entries = Vec::new(&s.env);
+    for (seed, amount) in (first_seed..).zip(amounts) {
+        entries.push_back((register(s, seed, 1_000), *amount));
+    }
+    entries
+}
+
+#[test]
+fn fund_missions_pays_each_mission_under_one_signature() {
+    let s = setup();
+    let collector = Address::generate(&s.env);
+    s.contract.set_role(&Role::FeeCollector, &Some(collector.clone()));
+    s.contract.set_fee_bps(&100);
+    let entries = basket(&s, 1, &[200, 1_000]);
+    let donor = Address::generate(&s.env);
+    s.token_admin.mint(&donor, &2_000);
+
+    let token_ids = s.contract.fund_missions(&donor, &entries);
+    assert_eq!(
+        s.env.auths(),
+        std::vec![(
+            donor.clone(),
+            AuthorizedInvocation {
+                function: AuthorizedFunction::Contract((
+                    s.contract.address.clone(),
+                    Symbol::new(&s.env, "fund_missions"),
+                    (donor.clone(), entries.clone()).into_val(&s.env),
+                )),
+                sub_invocations: std::vec![
+                    AuthorizedInvocation {
+                        function: AuthorizedFunction::Contract((
+                            s.token.address.clone(),
+                            symbol_short!("transfer"),
+                            (donor.clone(), s.contract.address.clone(), 1_188_i128).into_val(&s.env),
+                        )),
+                        sub_invocations: std::vec![],
+                    },
+                    AuthorizedInvocation {
+                        function: AuthorizedFunction::Contract((
+                            s.token.address.clone(),
+                            symbol_short!("transfer"),
+                            (donor.clone(), collector.clone(), 12_i128).into_val(&s.env),
+                        )),
+                        sub_invocations: std::vec![],
+                    },
+                ],
+            }
+        )]
+    );
+    assert_eq!(token_ids, vec![&s.env, 0, 1]);
+    assert_eq!(s.token.balance(&donor), 800);
+    assert_eq!(s.token.balance(&collector), 12);
+    assert_eq!(s.token.balance(&s.contract.address), 1_188);
+    for ((id, amount), token_id) in entries.iter().zip(token_ids.iter()) {
+        let net = amount - amount / 100;
+        assert_eq!(s.contract.get_mission(&id).funded_amount, net);
+        assert_eq!(contribution_of(&s, token_id), net);
+    }
+    assert_eq!(s.contract.tokens_of(&donor, &0, &10), token_ids);
+}
+
+#[test]
+fn fund_missions_is_all_or_nothing() {
+    let s = setup();
+    let donor = Address::generate(&s.env);
+    s.token_admin.mint(&donor, &1_000);
+    let entries = basket(&s, 1, &[100, 100]);
+    let (closed, _) = entries.get(1).unwrap();
+    s.contract.close_mission(&closed);
+
+    assert_eq!(
+        s.contract.try_fund_missions(&donor, &entries),
+        Err(Ok(Error::MissionClosed))
+    );
+    assert_eq!(s.token.balance(&donor), 1_000);
+    let (open, _) = entries.get(0).unwrap();
+    assert_eq!(s.contract.get_mission(&open).funded_amount, 0);
+
+    assert_eq!(
+        s.contract.try_fund_missions(&donor, &vec![&s.env]),
+        Err(Ok(Error::InvalidConfig))
+    );
+    assert_eq!(
+        s.contract
+            .try_fund_missions(&donor, &vec![&s.env, (open.clone(), 100), (open.clone(), 50)]),
+        Err(Ok(Error::InvalidConfig))
+    );
+    assert_eq!(
+        s.contract
+            .try_fund_missions(&donor, &vec![&s.env, (open.clone(), 100), (closed.clone(), -5)]),
+        Err(Ok(Error::InvalidAmount))
+    );
+    let too_many = basket(&s, 10, &[10; batch::MAX_BATCH_FUNDING as usize + 1]);
+    assert_eq!(
+        s.contract.try_fund_missions(&donor, &too_many),
+        Err(Ok(Error::BatchTooLarge))
+    );
+}
+
+/// A full batch of top-ups, the cheapest entries there are, fits the
+/// network limits even with a fee to pay, and the entry it would take to
+/// grow it by one does not.
+#[test]
+fn full_batch_fits_the_network_limits() {
+    let s = setup();
+    s.contract.set_role(&Role::FeeCollector, &Some(Address::generate(&s.env)));
+    s.contract.set_fee_bps(&250);
+    let max = batch::MAX_BATCH_FUNDING as usize;
+    let donor = Address::generate(&s.env);
+    s.token_admin.mint(&donor, &10_000);
+    let entries = basket(&s, 0, &std::vec![100; max]);
+    s.contract.fund_missions(&donor, &entries.slice(..2));
+    s.contract.fund_missions(&donor, &entries.slice(2..));
+    let write_entries = |size: u32| {
+        s.contract.fund_missions(&donor, &entries.slice(..size));
+        s.env.cost_estimate().resources().write_entries
+    };
+
+    let smaller = write_entries(max as u32 - 1);
+    let full = write_entries(max as u32);
+    assert_within_budget(&s, "fund_missions");
+    assert!(
+        full + (full - smaller) > TX_WRITE_ENTRIES_LIMIT,
+        "MAX_BATCH_FUNDING could be raised"
+    );
+}
+
+/// The costliest entry a batch can hold: a new donor to a mission in an
+/// open matching round whose gift reaches the target and leaves its
+/// callback pending, with a fee to pay. Batches are priced at that worst
+/// case, so one such entry alongside a cheaper one fits the limits and two
+/// are refused before anything moves.
+#[test]
+fn worst_case_batch_fits_the_network_limits() {
+    let s = setup();
+    s.contract.set_role(&Role::FeeCollector, &Some(Address::generate(&s.env)));
+    s.contract.set_fee_bps(&250);
+    let (round, _) = open_round(&s, 1_000, 0, 5_000);
+    let costly = |seed| {
+        let id = register(&s, seed, 100);
+        s.contract.join_round(&round, &id);
+        partner(&s, &id);
+        id
+    };
+    let (first, second) = (costly(1), costly(2));
+    let cheap = register(&s, 3, 1_000);
+    let donor = Address::generate(&s.env);
+    s.token_admin.mint(&donor, &10_000);
+
+    assert_eq!(
+        s.contract
+            .try_fund_missions(&donor, &vec![&s.env, (first.clone(), 200), (second, 200)]),
+        Err(Ok(Error::BatchTooLarge))
+    );
+    assert_eq!(s.token.balance(&donor), 10_000);
+
+    s.contract
+        .fund_missions(&donor, &vec![&s.env, (first.clone(), 200), (cheap, 200)]);
+    assert_within_budget(&s, "fund_missions at its worst");
+    assert_eq!(s.contract.get_round_entry(&round, &first).donors, 1);
+    assert!(s.env.as_contract(&s.contract.address, || {
+        s.env
+            .storage()
+            .persistent()
+            .has(&DataKey::PendingCallback(first.clone()))
+    }));
+}
+
+fn pledge(s: &Setup, id: &BytesN<32>, amount: i128, period: u64, cap: i128) -> Address {
+    let donor = Address::generate(&s.env);
+    s.token_admin.mint(&donor, &cap);
+    s.token.approve(&donor, &s.contract.address, &cap, &1_000);
+    s.contract
+        .create_pledge(id, &donor, &s.token.address, &amount, &period, &cap);
+    donor
+}
+
+#[test]
+fn pledge_is_collected_each_period_up_to_the_cap() {
+    let s = setup();
+    let id = register(&s, 1, 1_000);
+    let donor = pledge(&s, &id, 100, 1_000, 250);
+    assert_eq!(s.contract.get_donor_pledges(&donor), vec![&s.env, id.clone()]);
+
+    // Collection needs nobody's signature; the allowance covers it.
+    s.env.set_auths(&[]);
+    s.contract.collect_pledge(&id, &donor);
+    assert!(contract_events(&s).contains((
+        s.contract.address.clone(),
+        (symbol_short!("mission"), symbol_short!("collect"), id.clone()).into_val(&s.env),
+        (donor.clone(), 100i128, 100i128).into_val(&s.env),
+    )));
+    assert_eq!(
+        s.contract.try_collect_pledge(&id, &donor),
+        Err(Ok(Error::PledgeNotDue))
+    );
+
+    s.env.ledger().with_mut(|li| li.timestamp = 999);
+    assert_eq!(
+        s.contract.try_collect_pledge(&id, &donor),
+        Err(Ok(Error::PledgeNotDue))
+    );
+    s.env.ledger().with_mut(|li| li.timestamp = 1_000);
+    s.contract.collect_pledge(&id, &donor);
+    let pledge = s.contract.get_pledge(&id, &donor);
+    assert_eq!((pledge.collected, pledge.next_due), (200, 2_000));
+
+    // The last installment is whatever is left of the cap.
+    s.env.ledger().with_mut(|li| li.timestamp = 2_500);
+    let token_id = s.contract.collect_pledge(&id, &donor);
+    assert_eq!(contribution_of(&s, token_id), 50);
+    assert_eq!(
+        s.contract.try_get_pledge(&id, &donor),
+        Err(Ok(Error::PledgeNotFound))
+    );
+    assert!(s.contract.get_donor_pledges(&donor).is_empty());
+
+    assert_eq!(s.token.balance(&donor), 0);
+    assert_eq!(s.contract.get_mission(&id).funded_amount, 250);
+    assert_eq!(s.contract.get_contribution(&id, &donor).count, 3);
+    assert_eq!(s.token.allowance(&donor, &s.contract.address), 0);
+}
+
+#[test]
+fn pledge_catches_up_on_missed_periods_until_cancelled() {
+    let s = setup();
+    let id = register(&s, 1, 10_000);
+    let donor = pledge(&s, &id, 100, 1_000, 1_000);
+
+    s.env.ledger().with_mut(|li| li.timestamp = 2_000);
+    for _ in 0..3 {
+        s.contract.collect_pledge(&id, &donor);
+    }
+    assert_eq!(
+        s.contract.try_collect_pledge(&id, &donor),
+        Err(Ok(Error::PledgeNotDue))
+    );
+    assert_eq!(s.contract.get_pledge(&id, &donor).collected, 300);
+
+    sign_as(&s, &s.owner, "cancel_pledge", (id.clone(), donor.clone()).into_val(&s.env));
+    assert!(s.contract.try_cancel_pledge(&id, &donor).is_err());
+    s.env.mock_all_auths();
+    s.contract.cancel_pledge(&id, &donor);
+    s.env.ledger().with_mut(|li| li.timestamp = 5_000);
+    assert_eq!(
+        s.contract.try_collect_pledge(&id, &donor),
+        Err(Ok(Error::PledgeNotFound))
+    );
+    assert_eq!(s.token.balance(&donor), 700);
+}
+
+#[test]
+fn pledge_rules() {
+    let s = setup();
+    let id = register(&s, 1, 1_000);
+    let donor = Address::generate(&s.env);
+    let token = s.token.address.clone();
+    assert_eq!(
+        s.contract.try_create_pledge(&id, &donor, &token, &0, &1_000, &500),
+        Err(Ok(Error::InvalidAmount))
+    );
+    assert_eq!(
+        s.contract.try_create_pledge(&id, &donor, &token, &100, &1_000, &50),
+        Err(Ok(Error::InvalidAmount))
+    );
+    assert_eq!(
+        s.contract.try_create_pledge(&id, &donor, &token, &100, &0, &500),
+        Err(Ok(Error::InvalidConfig))
+    );
+    let (usdc, _) = extra_asset(&s, 1);
+    assert_eq!(
+        s.contract.try_create_pledge(&id, &donor, &usdc.address, &100, &1_000, &500),
+        Err(Ok(Error::UnsupportedAsset))
+    );
+
+    s.contract.create_pledge(&id, &donor, &token, &100, &1_000, &500);
+    assert_eq!(
+        s.contract.try_create_pledge(&id, &donor, &token, &100, &1_000, &500),
+        Err(Ok(Error::AlreadyExists))
+    );
+    // Without an allowance nothing can be pulled.
+    s.token_admin.mint(&donor, &500);
+    assert!(s.contract.try_collect_pledge(&id, &donor).is_err());
+    assert_eq!(s.token.balance(&donor), 500);
+
+    s.env.ledger().with_mut(|li| li.timestamp = DEADLINE + 1);
+    s.token.approve(&donor, &s.contract.address, &500, &1_000);
+    assert_eq!(
+        s.contract.try_collect_pledge(&id, &donor),
+        Err(Ok(Error::DeadlinePassed))
+    );
+}
+
+fn open_round(s: &Setup, pool: i128, start: u64, end: u64) -> (u32, Address) {
+    let sponsor = Address::generate(&s.env);
+    s.token_admin.mint(&sponsor, &pool);
+    (s.contract.create_round(&sponsor, &pool, &start, &end), sponsor)
+}
+
+fn donate(s: &Setup, id: &BytesN<32>, amount: i128) -> Address {
+    let donor = Address::generate(&s.env);
+    s.token_admin.mint(&donor, &amount);
+    s.contract.fund_mission(id, &donor, &amount);
+    donor
+}
+
+#[test]
+fn matching_round_splits_the_pool_quadratically() {
+    let s = setup();
+    let (round, sponsor) = open_round(&s, 1_000, 100, 5_000);
+    assert_eq!(s.token.balance(&s.contract.address), 1_000);
+    let solo = register(&s, 1, 10_000);
+    let broad = register(&s, 2, 10_000);
+    let pair = register(&s, 3, 10_000);
+    let outsider = register(&s, 4, 10_000);
+    for id in [&solo, &broad, &pair] {
+        s.contract.join_round(&round, id);
+    }
+
+    // Before the window opens nothing counts.
+    donate(&s, &pair, 900);
+    s.env.ledger().with_mut(|li| li.timestamp = 100);
+    donate(&s, &solo, 400);
+    for _ in 0..4 {
+        donate(&s, &broad, 100);
+    }
+    // A donor giving twice counts once, with their total.
+    let repeat = donate(&s, &pair, 50);
+    s.token_admin.mint(&repeat, &50);
+    s.contract.fund_mission(&pair, &repeat, &50);
+    donate(&s, &pair, 100);
+    donate(&s, &outsider, 5_000);
+
+    let entry = s.contract.get_round_entry(&round, &pair);
+    assert_eq!((entry.donors, entry.total, entry.sqrt_sum), (2, 200, 20));
+    assert_eq!(
+        s.contract.try_get_round_entry(&round, &outsider),
+        Err(Ok(Error::RoundNotFound))
+    );
+    assert_eq!(s.contract.try_finalize_round(&round), Err(Ok(Error::RoundOpen)));
+    assert_eq!(s.contract.try_claim_match(&round, &broad), Err(Ok(Error::RoundOpen)));
+
+    // Weights: solo 20² - 400 = 0, broad 40² - 400 = 1200, pair 20² - 200 = 200.
+    s.env.ledger().with_mut(|li| li.timestamp = 5_001);
+    assert_eq!(s.contract.finalize_round(&round), 999);
+    assert!(contract_events(&s).contains((
+        s.contract.address.clone(),
+        (symbol_short!("round"), symbol_short!("finalize"), round).into_val(&s.env),
+        (999i128, 1i128).into_val(&s.env),
+    )));
+    assert_eq!(s.token.balance(&sponsor), 1);
+    assert_eq!(s.contract.get_round_entry(&round, &solo).matched, 0);
+    assert_eq!(s.contract.get_round_entry(&round, &pair).matched, 142);
+    assert_eq!(s.contract.try_finalize_round(&round), Err(Ok(Error::RoundEnded)));
+
+    // Matches are only paid to missions that reach their target.
+    assert_eq!(s.contract.try_claim_match(&round, &broad), Err(Ok(Error::NotFunded)));
+    donate(&s, &broad, 9_600);
+    assert_eq!(s.contract.claim_match(&round, &broad), 857);
+    assert_eq!(s.token.balance(&s.owner), 857);
+    assert_eq!(s.contract.get_round_entry(&round, &broad).paid, 857);
+    assert_eq!(s.contract.try_claim_match(&round, &broad), Err(Ok(Error::NothingToClaim)));
+    // Matches are paid on top of what donors gave.
+    assert_eq!(s.contract.get_mission(&broad).funded_amount, 10_000);
+
+    // A mission that expires hands its match back to the sponsor.
+    s.env.ledger().with_mut(|li| li.timestamp = DEADLINE + 1);
+    assert_eq!(s.contract.try_claim_match(&round, &solo), Err(Ok(Error::NothingToClaim)));
+    assert_eq!(s.contract.claim_match(&round, &pair), 142);
+    assert_eq!(s.token.balance(&sponsor), 143);
+    assert_eq!(s.token.balance(&s.owner), 857);
+}
+
+#[test]
+fn matching_round_rules() {
+    let s = setup();
+    let sponsor = Address::generate(&s.env);
+    s.token_admin.mint(&sponsor, &1_000);
+    assert_eq!(
+        s.contract.try_create_round(&sponsor, &0, &0, &5_000),
+        Err(Ok(Error::InvalidAmount))
+    );
+    assert_eq!(
+        s.contract.try_create_round(&sponsor, &1_000, &5_000, &5_000),
+        Err(Ok(Error::InvalidDeadline))
+    );
+    assert_eq!(s.contract.try_get_round(&0), Err(Ok(Error::RoundNotFound)));
+
+    let round = s.contract.create_round(&sponsor, &1_000, &0, &5_000);
+    let id = register(&s, 1, 1_000);
+    s.contract.join_round(&round, &id);
+    assert_eq!(s.contract.get_round(&round).missions, vec![&s.env, id.clone()]);
+    assert_eq!(
+        s.contract.try_join_round(&round, &id),
+        Err(Ok(Error::AlreadyExists))
+    );
+
+    let stranger = Address::generate(&s.env);
+    let late = register(&s, 2, 1_000);
+    sign_as(&s, &stranger, "join_round", (round, late.clone()).into_val(&s.env));
+    assert!(s.contract.try_join_round(&round, &late).is_err());
+    s.env.mock_all_auths();
+
+    s.env.ledger().with_mut(|li| li.timestamp = 5_001);
+    assert_eq!(
+        s.contract.try_join_round(&round, &late),
+        Err(Ok(Error::RoundEnded))
+    );
+    // With nobody to match the whole pool goes back.
+    assert_eq!(s.contract.finalize_round(&round), 0);
+    assert_eq!(s.token.balance(&sponsor), 1_000);
+}
+
+#[test]
+fn matching_round_skips_refunds_and_cancelled_missions() {
+    let s = setup();
+    let (round, sponsor) = open_round(&s, 900, 0, 5_000);
+    let kept = register(&s, 1, 200);
+    let cancelled = register(&s, 2, 10_000);
+    s.contract.join_round(&round, &kept);
+    s.contract.join_round(&round, &cancelled);
+    for _ in 0..2 {
+        donate(&s, &kept, 100);
+    }
+    let donor = donate(&s, &cancelled, 100);
+    donate(&s, &cancelled, 100);
+
+    s.contract.cancel_mission(&cancelled);
+    s.contract.claim_refund(&cancelled, &donor);
+    let entry = s.contract.get_round_entry(&round, &cancelled);
+    assert_eq!((entry.donors, entry.total, entry.sqrt_sum), (1, 100, 10));
+
+    s.env.ledger().with_mut(|li| li.timestamp = 5_001);
+    assert_eq!(s.contract.finalize_round(&round), 900);
+    assert_eq!(s.contract.get_round_entry(&round, &cancelled).matched, 0);
+    assert_eq!(s.contract.claim_match(&round, &kept), 900);
+    assert_eq!(s.token.balance(&sponsor), 0);
+}
+
+#[test]
+fn match_is_released_with_the_missions_escrow() {
+    let s = setup();
+    let (round, sponsor) = open_round(&s, 1_000, 0, 5_000);
+    let reviewer = Address::generate(&s.env);
+    let plan = vec![&s.env, milestone(&s, "Design", 50), milestone(&s, "Launch", 50)];
+    let id = register_with_milestones(&s, 1, 200, plan, Some(reviewer));
+    s.contract.join_round(&round, &id);
+    donate(&s, &id, 100);
+    donate(&s, &id, 100);
+    s.env.ledger().with_mut(|li| li.timestamp = 5_001);
+    assert_eq!(s.contract.finalize_round(&round), 1_000);
+
+    // Nothing is earned until a milestone releases funds.
+    assert_eq!(s.contract.try_claim_match(&round, &id), Err(Ok(Error::NothingToClaim)));
+    s.contract.submit_milestone(&id, &0, &BytesN::from_array(&s.env, &[1; 32]));
+    s.contract.review_milestone(&id, &0, &true);
+    assert_eq!(s.contract.claim_match(&round, &id), 500);
+    assert_eq!(s.token.balance(&s.owner), 600);
+
+    // Cancelling sends the unearned half back to the sponsor.
+    s.contract.cancel_mission(&id);
+    assert_eq!(s.contract.claim_match(&round, &id), 500);
+    assert_eq!(s.token.balance(&sponsor), 500);
+    assert_eq!(s.contract.try_claim_match(&round, &id), Err(Ok(Error::NothingToClaim)));
+}
+
+#[test]
+fn full_round_finalizes_within_the_network_budget() {
+    let s = setup();
+    let (round, _) = open_round(&s, 1_000, 0, 5_000);
+    for seed in 0..matching::MAX_ROUND_MISSIONS as u8 {
+        let id = register(&s, seed, 1_000);
+        s.contract.join_round(&round, &id);
+        donate(&s, &id, 100);
+        donate(&s, &id, 100);
+    }
+    let late = register(&s, 100, 1_000);
+    assert_eq!(s.contract.try_join_round(&round, &late), Err(Ok(Error::RoundFull)));
+
+    s.env.ledger().with_mut(|li| li.timestamp = 5_001);
+    s.contract.finalize_round(&round);
+    assert_within_budget(&s, "finalize_round");
+}
+
+/// Random sequences of donations, refunds, withdrawals and time jumps
+/// against a couple of missions, checking after every step that the books
+/// balance and nothing goes negative, whether or not the step succeeded.
+mod invariants {
+    use super::*;
+    use proptest::prelude::*;
+
+    const MISSIONS: usize = 2;
+    const DONORS: usize = 3;
+    const MINTED: i128 = 5_000;
+
+    #[derive(Clone, Debug)]
+    enum Op {
+        Fund { mission: usize, donor: usize, amount: i128 },
+        Refund { mission: usize, donor: usize },
+        Withdraw { mission: usize },
+        Advance(u64),
+    }
+
+    fn amount() -> impl Strategy<Value = i128> {
+        prop_oneof![
+            8 => -100i128..=1_200,
+            1 => Just(0),
+            1 => Just(i128::MIN),
+            1 => Just(i128::MAX),
+            1 => any::<i128>(),
+        ]
+    }
+
+    fn op() -> impl Strategy<Value = Op> {
+        prop_oneof![
+            6 => (0..MISSIONS, 0..DONORS, amount())
+                .prop_map(|(mission, donor, amount)| Op::Fund { mission, donor, amount }),
+            2 => (0..MISSIONS, 0..DONORS).prop_map(|(mission, donor)| Op::Refund { mission, donor }),
+            1 => (0..MISSIONS).prop_map(|mission| Op::Withdraw { mission }),
+            1 => (1..DEADLINE).prop_map(Op::Advance),
+        ]
+    }
+
+    fn contribution(s: &Setup, id: &BytesN<32>, donor: &Address) -> Option<Contribution> {
+        s.contract
+            .try_get_contribution(id, donor)
+            .ok()
+            .map(|contribution| contribution.unwrap())
+    }
+
+    fn check(s: &Setup, ids: &[BytesN<32>], donors: &[Address]) -> Result<(), TestCaseError> {
+        let mut raised = 0;
+        let mut withdrawn = 0;
+        for id in ids {
+            let mission = s.contract.get_mission(id);
+            prop_assert!(mission.funded_amount >= 0);
+            prop_assert!(mission.withdrawn >= 0);
+            prop_assert!(mission.withdrawn <= mission.funded_amount);
+
+            let mut contributed = 0;
+            for donor in donors {
+                if let Some(contribution) = contribution(s, id, donor) {
+                    prop_assert!(contribution.amount >= 0);
+                    prop_assert!(contribution.refunded >= 0);
+                    contributed += contribution.amount;
+                }
+            }
+            prop_assert_eq!(contributed, mission.funded_amount);
+
+            let funds = mission.funds.get(s.token.address.clone()).unwrap();
+            prop_assert_eq!(funds.raised, mission.funded_amount);
+            prop_assert_eq!(funds.held, mission.funded_amount - mission.withdrawn);
+            raised += mission.funded_amount;
+            withdrawn += mission.withdrawn;
+        }
+        prop_assert_eq!(raised, s.token.balance(&s.contract.address) + withdrawn);
+        prop_assert_eq!(withdrawn, s.token.balance(&s.owner));
+
+        let mut held_by_donors = 0;
+        for donor in donors {
+            let balance = s.token.balance(donor);
+            prop_assert!(balance >= 0);
+            held_by_donors += balance;
+        }
+        prop_assert_eq!(held_by_donors + raised, MINTED * DONORS as i128);
+        Ok(())
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(48))]
+
+        #[test]
+        fn accounting_balances_after_any_sequence(ops in prop::collection::vec(op(), 1..30)) {
+            let s = setup();
+            let ids: std::vec::Vec<_> = (0..MISSIONS as u8)
+                .map(|seed| register(&s, seed, 1_000))
+                .collect();
+            let donors: std::vec::Vec<_> = (0..DONORS)
+                .map(|_| {
+                    let donor = Address::generate(&s.env);
+                    s.token_admin.mint(&donor, &MINTED);
+                    donor
+                })
+                .collect();
+
+            for op in ops {
+                match op {
+                    Op::Fund { mission, donor, amount } => {
+                        let result = s.contract.try_fund_mission(&ids[mission], &donors[donor], &amount);
+                        if amount <= 0 {
+                            prop_assert_eq!(result, Err(Ok(Error::InvalidAmount)));
+                        }
+                    }
+                    Op::Refund { mission, donor } => {
+                        let _ = s.contract.try_claim_refund(&ids[mission], &donors[donor]);
+                    }
+                    Op::Withdraw { mission } => {
+                        let _ = s.contract.try_withdraw(&ids[mission]);
+                    }
+                    Op::Advance(seconds) => {
+                        s.env.ledger().with_mut(|li| li.timestamp += seconds);
+                    }
+                }
+                check(&s, &ids, &donors)?;
+            }
+        }
+    }
+}
+
+mod v1 {
+    // Release before platform fees, which predates `upgrade` and the
+    // recorded schema version.
+    soroban_sdk::contractimport!(file = "testdata/fund_prog_v1.wasm");
+}
+
+mod v2 {
+    soroban_sdk::contractimport!(file = "testdata/fund_prog_v2.wasm");
+}
+
+mod v3 {
+    soroban_sdk::contractimport!(file = "testdata/fund_prog_v3.wasm");
+}
+
+mod v4 {
+    soroban_sdk::contractimport!(file = "testdata/fund_prog_v4.wasm");
+}
+
+mod v5 {
+    soroban_sdk::contractimport!(file = "testdata/fund_prog_v5.wasm");
+}
+
+#[test]
+fn upgrade_from_v1_build_migrates_missions() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let owner = Address::generate(&env);
+    let donor = Address::generate(&env);
+    let sac = env.register_stellar_asset_contract_v2(admin.clone());
+    token::StellarAssetClient::new(&env, &sac.address()).mint(&donor, &1_000);
+
+    let contract_id = env.register(v1::WASM, ());
+    let old = v1::Client::new(&env, &contract_id);
+    old.initialize(&admin, &sac.address());
+    let ids = [1u8, 2, 3].map(|seed| BytesN::from_array(&env, &[seed; 32]));
+    for id in ids.iter() {
+        old.register_mission(
+            id,
+            &String::from_str(&env, "Lunar Rover"),
+            &String::from_str(&env, "Regolith sampling rover"),
+            &String::from_str(&env, "ipfs://rover.png"),
+            &500,
+            &DEADLINE,
+            &owner,
+            &Vec::new(&env),
+            &None,
+        );
+    }
+    old.fund_mission(&ids[1], &donor, &300);
+
+    // v1 has no `upgrade` entry point, so swap its code from inside the
+    // contract the way `upgrade` does.
+    let v2_hash = env.deployer().upload_contract_wasm(v2::WASM);
+    env.as_contract(&contract_id, || {
+        env.deployer().update_current_contract_wasm(v2_hash.clone())
+    });
+
+    let contract = v2::Client::new(&env, &contract_id);
+    assert_eq!(contract.schema_version(), 1);
+    assert_eq!(
+        contract.try_fund_mission(&ids[1], &donor, &100),
+        Err(Ok(soroban_sdk::Error::from_contract_error(
+            Error::MigrationPending as u32
+        )))
+    );
+    assert!(!contract.migrate(&2));
+    assert!(contract.migrate(&2));
+    assert_eq!(contract.schema_version(), 2);
+    assert_eq!(contract.get_mission(&ids[1]).fees_paid, 0);
+
+    // From v2 on the contract upgrades itself.
+    contract.upgrade(&env.deployer().upload_contract_wasm(v3::WASM));
+    let contract = v3::Client::new(&env, &contract_id);
+    assert!(contract.migrate(&10));
+    assert_eq!(contract.get_mission(&ids[1]).status, v3::MissionStatus::Active);
+
+    contract.upgrade(&env.deployer().upload_contract_wasm(v4::WASM));
+    let contract = v4::Client::new(&env, &contract_id);
+    assert!(contract.migrate(&10));
+    let mission = contract.get_mission(&ids[1]);
+    assert_eq!(mission.funded_amount, 300);
+    assert_eq!(
+        mission.funds.get(sac.address()),
+        Some(v4::AssetFunds { raised: 300, held: 300 })
+    );
+    assert_eq!(mission.deadline, DEADLINE);
+    assert_eq!(mission.status, v4::MissionStatus::Active);
+
+    // Version 4 kept token, owner and donor lists whole.
+    let other = Address::generate(&env);
+    token::StellarAssetClient::new(&env, &sac.address()).mint(&other, &1_000);
+    contract.fund_mission(&ids[0], &donor, &100);
+    contract.fund_mission(&ids[1], &other, &50);
+    contract.transfer(&donor, &other, &0);
+
+    // Parsing a build this size on upload costs more than the default
+    // test budget allows.
+    env.cost_estimate().budget().reset_unlimited();
+    contract.upgrade(&env.deployer().upload_contract_wasm(v5::WASM));
+    let contract = MissionFundContractClient::new(&env, &contract_id);
+    assert!(!contract.migrate(&2));
+    assert_eq!(
+        contract.try_transfer(&donor, &other, &1),
+        Err(Ok(Error::MigrationPending))
+    );
+    while !contract.migrate(&2) {}
+    assert_eq!(contract.schema_version(), SCHEMA_VERSION);
+
+    assert_eq!(contract.owner_of(&0), other);
+    assert_eq!(contract.owner_of(&1), donor);
+    assert_eq!(contract.balance_of(&donor), 1);
+    assert_eq!(contract.tokens_of(&other, &0, &10), vec![&env, 2, 0]);
+    let owned: std::vec::Vec<_> = contract
+        .missions_by_owner(&owner, &0, &10)
+        .iter()
+        .map(|(id, _)| id)
+        .collect();
+    assert_eq!(owned, ids.to_vec());
+    assert_eq!(
+        contract.donor_missions(&donor, &0, &10),
+        vec![&env, ids[1].clone(), ids[0].clone()]
+    );
+    assert_eq!(contract.donor_missions(&other, &0, &10), vec![&env, ids[1].clone()]);
+    env.as_contract(&contract_id, || {
+        for list in [
+            ListKeyV4::OwnedNfts(other.clone()),
+            ListKeyV4::OwnerMissions(owner.clone()),
+            ListKeyV4::DonorMissions(donor.clone()),
+        ] {
+            assert!(!env.storage().persistent().has(&list));
+        }
+    });
+
+    // Migrated tokens and lists carry on as if minted by this build.
+    contract.transfer(&donor, &other, &1);
+    assert_eq!(contract.balance_of(&donor), 0);
+    assert_eq!(contract.tokens_of(&other, &0, &10), vec![&env, 2, 0, 1]);
+    assert_eq!(contract.mission_count(), 3);
+    contract.fund_mission(&ids[1], &donor, &150);
+    assert_eq!(contract.get_mission_status(&ids[1]), MissionStatus::Funded);
+}
+
+/// Puts the store back to schema version 1: missions without `fees_paid`
+/// or anything added since, each `funded` with nothing withdrawn.
+fn downgrade_to_v1(s: &Setup, ids: &[BytesN<32>], funded: i128) {
+    s.env.as_contract(&s.contract.address, || {
+        for id in ids.iter() {
+            let mission = load_mission(&s.env, id).unwrap();
+            let old = MissionV1 {
+                title: mission.title,
+                desc: mission.desc,
+                image_url: mission.image_url,
+                target_amount: mission.target_amount,
+                funded_amount: funded,
+                owner: mission.owner,
+                deadline: mission.deadline,
+                withdrawn: 0,
+            };
+            storage::write(&s.env, &DataKey::Mission(id.clone()), &old);
+        }
+        s.env.storage().instance().set(&DataKey::SchemaVersion, &1u32);
+    });
+}
+
+#[test]
+fn migrate_resumes_across_batches() {
+    let s = setup();
+    let ids = [1u8, 2, 3].map(|seed| register(&s, seed, 1_000));
+    downgrade_to_v1(&s, &ids, 250);
+
+    assert_eq!(
+        s.contract.try_register_mission(
+            &BytesN::from_array(&s.env, &[9; 32]),
+            &String::from_str(&s.env, "Late"),
+            &String::from_str(&s.env, "Registered mid-migration"),
+            &String::from_str(&s.env, "ipfs://late.png"),
+            &1_000,
+            &DEADLINE,
+            &s.owner,
+            &Vec::new(&s.env),
+            &None,
+            &None,
+        ),
+        Err(Ok(Error::MigrationPending))
+    );
+
+    // Three missions a call at a time, once per schema step.
+    let mut calls = 1;
+    while !s.contract.migrate(&1) {
+        calls += 1;
+    }
+    assert_eq!(calls, 12);
+    assert_eq!(
+        contract_events(&s).slice(0..1),
+        vec![
+            &s.env,
+            (
+                s.contract.address.clone(),
+                (symbol_short!("admin"), symbol_short!("schema")).into_val(&s.env),
+                SCHEMA_VERSION.into_val(&s.env),
+            ),
+        ]
+    );
+    for id in ids.iter() {
+        assert_eq!(s.contract.get_mission(id).funded_amount, 250);
+    }
+    register(&s, 9, 1_000);
+}
+
+/// Between `upgrade` and the last `migrate` call, missions may still be in
+/// an older layout, so every call that reads or writes one fails cleanly
+/// instead of misreading it.
+#[test]
+fn mission_calls_wait_for_the_migration() {
+    let s = setup();
+    let id = register(&s, 1, 1_000);
+    let team_id = register(&s, 2, 1_000);
+    let (round, _) = open_round(&s, 1_000, 0, 5_000);
+    s.contract.join_round(&round, &id);
+    s.contract
+        .set_team(&team_id, &vec![&s.env, s.owner.clone()], &1, &s.owner);
+    let proposal = s.contract.propose(&team_id, &s.owner, &TeamAction::Close);
+    let donor = donate(&s, &id, 100);
+    downgrade_to_v1(&s, &[id.clone(), team_id.clone()], 100);
+
+    let pending = Some(Ok(Error::MigrationPending));
+    let text = String::from_str(&s.env, "Mid-migration");
+    let evidence = BytesN::from_array(&s.env, &[1; 32]);
+    let assets = vec![&s.env, s.token.address.clone()];
+    assert_eq!(s.contract.try_fund_mission(&id, &donor, &10).err(), pending);
+    let entries = vec![&s.env, (id.clone(), 10)];
+    assert_eq!(s.contract.try_fund_missions(&donor, &entries).err(), pending);
+    assert_eq!(s.contract.try_claim_refund(&id, &donor).err(), pending);
+    assert_eq!(s.contract.try_withdraw(&id).err(), pending);
+    assert_eq!(s.contract.try_get_remaining_funds(&id).err(), pending);
+    assert_eq!(s.contract.try_get_mission(&id).err(), pending);
+    assert_eq!(s.contract.try_get_mission_status(&id).err(), pending);
+    assert_eq!(s.contract.try_get_contribution_assets(&id, &donor).err(), pending);
+    assert_eq!(s.contract.try_set_mission_paused(&id, &true).err(), pending);
+    assert_eq!(s.contract.try_set_mission_assets(&id, &s.token.address, &assets).err(), pending);
+    assert_eq!(s.contract.try_set_target_callback(&id, &None).err(), pending);
+    assert_eq!(s.contract.try_notify_target(&id).err(), pending);
+    assert_eq!(
+        s.contract
+            .try_update_mission_metadata(&id, &text, &text, &text)
+            .err(),
+        pending
+    );
+    assert_eq!(s.contract.try_close_mission(&id).err(), pending);
+    assert_eq!(s.contract.try_cancel_mission(&id).err(), pending);
+    assert_eq!(s.contract.try_join_round(&round, &team_id).err(), pending);
+    assert_eq!(s.contract.try_finalize_round(&round).err(), pending);
+    assert_eq!(s.contract.try_claim_match(&round, &id).err(), pending);
+    assert_eq!(s.contract.try_submit_milestone(&id, &0, &evidence).err(), pending);
+    assert_eq!(s.contract.try_review_milestone(&id, &0, &true).err(), pending);
+    assert_eq!(
+        s.contract
+            .try_set_team(&id, &vec![&s.env, s.owner.clone()], &1, &s.owner)
+            .err(),
+        pending
+    );
+    assert_eq!(s.contract.try_execute_proposal(&team_id, &proposal).err(), pending);
+
+    while !s.contract.migrate(&10) {}
+    assert_eq!(s.contract.get_mission(&id).funded_amount, 100);
+    s.contract.execute_proposal(&team_id, &proposal);
+    assert_eq!(s.contract.get_mission(&team_id).status, MissionStatus::Closed);
+    s.contract.close_mission(&id);
+}
+
+/// Puts the token, owner and donor lists back into their schema version 4
+/// form, one whole list per owner or donor, and the tokens back to their
+/// version 4 layout.
+fn downgrade_to_v4(s: &Setup) {
+    fn whole_list<T: IntoVal<Env, Val> + TryFromVal<Env, Val>>(
+        env: &Env,
+        list: ListKeyV4,
+        count: DataKey,
+        at: impl Fn(u32) -> DataKey,
+    ) {
+        let storage = env.storage().persistent();
+        let Some(len) = storage.get::<_, u32>(&count) else {
+            return;
+        };
+        let mut items: Vec<T> = Vec::new(env);
+        for position in 0..len {
+            items.push_back(storage.get(&at(position)).unwrap());
+            storage.remove(&at(position));
+        }
+        storage.remove(&count);
+        storage.set(&list, &items);
+    }
+
+    let env = &s.env;
+    let (missions, tokens) = (s.contract.mission_count(), s.contract.total_supply());
+    env.as_contract(&s.contract.address, || {
+        for position in 0..missions {
+            let id: BytesN<32> = storage::read(env, &DataKey::MissionAt(position)).unwrap();
+            let owner = load_mission(env, &id).unwrap().owner;
+            whole_list::<BytesN<32>>(
+                env,
+                ListKeyV4::OwnerMissions(owner.clone()),
+                DataKey::OwnerMissionCount(owner.clone()),
+                |at| DataKey::OwnerMissionAt(owner.clone(), at),
+            );
+            for donor_position in 0..contribution::donor_count(env, &id) {
+                let donor: Address =
+                    storage::read(env, &DataKey::DonorAt(id.clone(), donor_position)).unwrap();
+                whole_list::<BytesN<32>>(
+                    env,
+                    ListKeyV4::DonorMissions(donor.clone()),
+                    DataKey::DonorMissionCount(donor.clone()),
+                    |at| DataKey::DonorMissionAt(donor.clone(), at),
+                );
+            }
+        }
+        for token_id in 0..tokens {
+            let nft: Nft = storage::read(env, &DataKey::Nft(token_id)).unwrap();
+            whole_list::<u64>(
+                env,
+                ListKeyV4::OwnedNfts(nft.owner.clone()),
+                DataKey::OwnedNftCount(nft.owner.clone()),
+                |at| DataKey::OwnedNftAt(nft.owner.clone(), at),
+            );
+            let old = NftV4 {
+                owner: nft.owner,
+                approved: nft.approved,
+                mission_id: nft.mission_id,
+                metadata: nft.metadata,
+            };
+            storage::write(env, &DataKey::Nft(token_id), &old);
+        }
+        env.storage().instance().set(&DataKey::SchemaVersion, &4u32);
+    });
+}
+
+#[test]
+fn list_migration_fits_the_network_budget() {
+    let s = setup();
+    let id = register(&s, 1, 100_000);
+    let donors: std::vec::Vec<_> = (0..upgrade::MAX_LIST_MIGRATION_BATCH)
+        .map(|_| donate(&s, &id, 10))
+        .collect();
+    downgrade_to_v4(&s);
+    assert_eq!(s.contract.balance_of(&donors[0]), 0);
+
+    // The mission's owner, each donor's list, then each token.
+    let mut calls = 0;
+    loop {
+        calls += 1;
+        let done = s.contract.migrate(&u32::MAX);
+        assert_within_budget(&s, "migrate to version 5");
+        if done {
+            break;
+        }
+    }
+    assert_eq!(calls, 3);
+    for (token_id, donor) in donors.iter().enumerate() {
+        assert_eq!(s.contract.tokens_of(donor, &0, &10), vec![&s.env, token_id as u64]);
+        assert_eq!(s.contract.donor_missions(donor, &0, &10), vec![&s.env, id.clone()]);
+    }
+    assert_eq!(s.contract.missions_by_owner(&s.owner, &0, &10).len(), 1);
+
+    // Migrated tokens follow their donor's tier like any other.
+    s.token_admin.mint(&donors[0], &5_990);
+    s.contract.fund_mission(&id, &donors[0], &5_990);
+    assert_eq!(rarity_of(&s, 0), String::from_str(&s.env, "Epic"));
+    assert_eq!(rarity_of(&s, 1), String::from_str(&s.env, "Common"));
+}
+
+#[test]
+fn upgrade_is_admin_only_and_keeps_state() {
+    let s = setup();
+    let id = register(&s, 1, 1_000);
+    let hash = s.env.deployer().upload_contract_wasm(v4::WASM);
+
+    let stranger = Address::generate(&s.env);
+    sign_as(&s, &stranger, "upgrade", (hash.clone(),).into_val(&s.env));
+    assert!(s.contract.try_upgrade(&hash).is_err());
+    s.env.mock_all_auths();
+
+    s.contract.upgrade(&hash);
+    assert_eq!(
+        contract_events(&s),
+        vec![
+            &s.env,
+            (
+                s.contract.address.clone(),
+                (symbol_short!("admin"), symbol_short!("upgrade")).into_val(&s.env),
+                hash.into_val(&s.env),
+            ),
+        ]
+    );
+    assert_eq!(s.contract.schema_version(), SCHEMA_VERSION);
+    assert_eq!(s.contract.get_mission(&id).target_amount, 1_000);
+    assert_eq!(s.contract.get_admin(), s.admin);
+}
+
@@ -0,0 +1,327 @@
+use soroban_sdk::{contractimpl, Address, BytesN, Env, IntoVal, Map, Symbol, TryFromVal, Val, Vec};
+
+use crate::asset::{self, AssetFunds};
+use crate::nft::Nft;
+use crate::storage::{self, DataKey, ListKeyV4, MissionV1, MissionV2, MissionV3, NftV4};
+use crate::{admin, contribution, events, load_mission, milestone, save_mission, Error, Mission, MissionStatus, MissionFundContract, MissionFundContractArgs, MissionFundContractClient};
+
+/// Layout version of the records this build reads and writes. Bump it
+/// whenever a stored type changes shape, together with a new step in
+/// `migrate_mission`.
+///
+/// 1. Missions with deadlines and withdrawals, before platform fees.
+/// 2. `Mission::fees_paid`.
+/// 3. `Mission::status` and `Mission::refund_pool`.
+/// 4. `Mission::reference_asset` and `Mission::funds`.
+/// 5. `Nft::donor` and `Nft::position`, with owner, donor and token lists
+///    kept one item per entry under `*Count`/`*At` keys.
+pub const SCHEMA_VERSION: u32 = 5;
+
+/// Upper bound on missions a single `migrate` call rewrites, so a large
+/// registry is migrated over several transactions instead of blowing the
+/// budget of one. Each mission is a ledger write, and a transaction may
+/// write no more than 25 entries.
+pub const MAX_MIGRATION_BATCH: u32 = 20;
+
+/// Upper bound on list items a single `migrate` call moves in the step to
+/// version 5. An item (a mission in its owner's list, a mission in a
+/// donor's list, or a token) costs up to four ledger writes.
+pub const MAX_LIST_MIGRATION_BATCH: u32 = 6;
+
+/// Initialized contracts with no recorded version were set up by a build
+/// that predates versioning and hold version 1 data.
+pub(crate) fn schema_version(env: &Env) -> u32 {
+    let instance = env.storage().instance();
+    instance.get(&DataKey::SchemaVersion).unwrap_or_else(|| {
+        if instance.has(&DataKey::Token) {
+            1
+        } else {
+            SCHEMA_VERSION
+        }
+    })
+}
+
+/// Fails while stored records are older than this build expects.
+pub(crate) fn ensure_current(env: &Env) -> Result<(), Error> {
+    if schema_version(env) < SCHEMA_VERSION {
+        return Err(Error::MigrationPending);
+    }
+    Ok(())
+}
+
+/// Version 3 missions only ever held the platform token. What the contract
+/// still holds for them is whatever was neither withdrawn nor released
+/// through milestones, or the refund pool once cancelled.
+pub(crate) fn from_v3(env: &Env, mission_id: &BytesN<32>, v3: MissionV3) -> Result<Mission, Error> {
+    let token = asset::platform_token(env)?;
+    let held = if v3.status == MissionStatus::Cancelled {
+        v3.refund_pool
+    } else {
+        v3.funded_amount - v3.withdrawn - milestone::released(env, mission_id)
+    };
+    let mut funds = Map::new(env);
+    funds.set(
+        token.clone(),
+        AssetFunds {
+            raised: v3.funded_amount,
+            held,
+        },
+    );
+    Ok(Mission {
+        title: v3.title,
+        desc: v3.desc,
+        image_url: v3.image_url,
+        target_amount: v3.target_amount,
+        funded_amount: v3.funded_amount,
+        owner: v3.owner,
+        deadline: v3.deadline,
+        withdrawn: v3.withdrawn,
+        fees_paid: v3.fees_paid,
+        status: v3.status,
+        refund_pool: v3.refund_pool,
+        reference_asset: token,
+        funds,
+    })
+}
+
+/// Rewrites the mission at index position `position` from layout `from` to
+/// layout `from + 1`.
+fn migrate_mission(env: &Env, from: u32, position: u32) -> Result<(), Error> {
+    let Some(mission_id) = storage::read::<BytesN<32>>(env, &DataKey::MissionAt(position)) else {
+        return Ok(());
+    };
+    let key = DataKey::Mission(mission_id.clone());
+    match from {
+        1 => {
+            if let Some(old) = storage::read::<MissionV1>(env, &key) {
+                storage::write(env, &key, &MissionV2::from(old));
+            }
+        }
+        2 => {
+            if let Some(old) = storage::read::<MissionV2>(env, &key) {
+                storage::write(env, &key, &MissionV3::from(old));
+            }
+        }
+        3 => {
+            if let Some(old) = storage::read::<MissionV3>(env, &key) {
+                save_mission(env, &mission_id, &from_v3(env, &mission_id, old)?);
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Moves `item` out of the version 4 list under `list` to the entry
+/// `at(position)`, at the place it held in that list, and returns that
+/// place. `count` tallies the items moved so far, so once it reaches the
+/// list's length every item is under its own entry and the list is
+/// dropped. Each item must therefore be moved exactly once. Lists that are
+/// already gone, as in a store that never held version 4 data, are skipped.
+fn move_to_entry<T>(
+    env: &Env,
+    list: &ListKeyV4,
+    count: &DataKey,
+    at: impl Fn(u32) -> DataKey,
+    item: &T,
+) -> Option<u32>
+where
+    T: IntoVal<Env, Val> + TryFromVal<Env, Val> + Clone,
+{
+    let items: Vec<T> = env.storage().persistent().get(list)?;
+    let position = items.first_index_of(item.clone())?;
+    storage::write(env, &at(position), item);
+    let moved: u32 = storage::read::<u32>(env, count).unwrap_or(0) + 1;
+    storage::write(env, count, &moved);
+    if moved >= items.len() {
+        env.storage().persistent().remove(list);
+    }
+    Some(position)
+}
+
+/// Puts the mission into its owner's list.
+fn move_owner_entry(env: &Env, mission_id: &BytesN<32>) {
+    let Ok(mission) = load_mission(env, mission_id) else {
+        return;
+    };
+    let owner = mission.owner;
+    move_to_entry(
+        env,
+        &ListKeyV4::OwnerMissions(owner.clone()),
+        &DataKey::OwnerMissionCount(owner.clone()),
+        |at| DataKey::OwnerMissionAt(owner.clone(), at),
+        mission_id,
+    );
+}
+
+/// Puts the mission into the list of its donor at `donor_position`.
+fn move_donor_entry(env: &Env, mission_id: &BytesN<32>, donor_position: u32) {
+    let key = DataKey::DonorAt(mission_id.clone(), donor_position);
+    let Some(donor) = storage::read::<Address>(env, &key) else {
+        return;
+    };
+    move_to_entry(
+        env,
+        &ListKeyV4::DonorMissions(donor.clone()),
+        &DataKey::DonorMissionCount(donor.clone()),
+        |at| DataKey::DonorMissionAt(donor.clone(), at),
+        mission_id,
+    );
+}
+
+/// Rewrites a version 4 token and puts it into its owner's list. Version
+/// 4 did not record who donated, so the holder at migration time stands
+/// in for the donor; for tokens never transferred that is the donor.
+fn migrate_nft(env: &Env, token_id: u64) {
+    let key = DataKey::Nft(token_id);
+    let Some(raw) = env.storage().persistent().get::<_, Map<Symbol, Val>>(&key) else {
+        return;
+    };
+    if raw.contains_key(Symbol::new(env, "position")) {
+        return;
+    }
+    let Ok(old) = NftV4::try_from_val(env, &raw.to_val()) else {
+        return;
+    };
+    let position = move_to_entry(
+        env,
+        &ListKeyV4::OwnedNfts(old.owner.clone()),
+        &DataKey::OwnedNftCount(old.owner.clone()),
+        |at| DataKey::OwnedNftAt(old.owner.clone(), at),
+        &token_id,
+    )
+    .unwrap_or(0);
+    let nft = Nft {
+        owner: old.owner.clone(),
+        approved: old.approved,
+        mission_id: old.mission_id,
+        donor: old.owner,
+        metadata: old.metadata,
+        position,
+    };
+    storage::write(env, &key, &nft);
+}
+
+/// Walks the step to version 5, item by item: first each mission, as its
+/// owner's list entry followed by an entry in each of its donors' lists,
+/// then each token. `MigrationCursor` holds the mission index position, or
+/// past the missions the mission count plus the token id, and
+/// `MigrationItem` the mission's next item: 0 for its owner, `n` for the
+/// donor at position `n - 1`. Returns whether every item has been moved.
+fn migrate_lists(env: &Env, limit: u32) -> bool {
+    let instance = env.storage().instance();
+    let missions: u32 = instance.get(&DataKey::MissionCount).unwrap_or(0);
+    let tokens: u64 = instance.get(&DataKey::NftCount).unwrap_or(0);
+    let mut cursor: u32 = instance.get(&DataKey::MigrationCursor).unwrap_or(0);
+    let mut item: u32 = instance.get(&DataKey::MigrationItem).unwrap_or(0);
+
+    for _ in 0..limit.min(MAX_LIST_MIGRATION_BATCH) {
+        if cursor < missions {
+            let Some(mission_id) = storage::read::<BytesN<32>>(env, &DataKey::MissionAt(cursor))
+            else {
+                cursor += 1;
+                continue;
+            };
+            if item == 0 {
+                move_owner_entry(env, &mission_id);
+            } else {
+                move_donor_entry(env, &mission_id, item - 1);
+            }
+            item += 1;
+            if item > contribution::donor_count(env, &mission_id) {
+                cursor += 1;
+                item = 0;
+            }
+        } else if u64::from(cursor - missions) < tokens {
+            migrate_nft(env, u64::from(cursor - missions));
+            cursor += 1;
+        } else {
+            break;
+        }
+    }
+
+    if cursor < missions || u64::from(cursor - missions) < tokens {
+        instance.set(&DataKey::MigrationCursor, &cursor);
+        instance.set(&DataKey::MigrationItem, &item);
+        return false;
+    }
+    instance.remove(&DataKey::MigrationItem);
+    true
+}
+
+/// Rewrites up to `limit` missions for the steps up to version 4, which
+/// rewrite each mission record in place. Returns whether every mission has
+/// been rewritten.
+fn migrate_missions(env: &Env, version: u32, limit: u32) -> Result<bool, Error> {
+    let total: u32 = env
+        .storage()
+        .instance()
+        .get(&DataKey::MissionCount)
+        .unwrap_or(0);
+    let cursor: u32 = env
+        .storage()
+        .instance()
+        .get(&DataKey::MigrationCursor)
+        .unwrap_or(0);
+    let end = total.min(cursor.saturating_add(limit.min(MAX_MIGRATION_BATCH)));
+    for position in cursor..end {
+        migrate_mission(env, version, position)?;
+    }
+    if end < total {
+        env.storage().instance().set(&DataKey::MigrationCursor, &end);
+        return Ok(false);
+    }
+    Ok(true)
+}
+
+#[contractimpl]
+impl MissionFundContract {
+    /// Replaces the contract code with the already uploaded wasm `new_wasm_hash`.
+    /// Storage and the contract id are kept; if the new build raises
+    /// `SCHEMA_VERSION`, call `migrate` until it returns `true` before using it.
+    /// Calls that read or write missions fail with `MigrationPending` until
+    /// then. Version 1 builds predate this entry point and cannot be
+    /// upgraded in place: such a deployment has to be redeployed with this
+    /// build as a new contract.
+    pub fn upgrade(env: Env, new_wasm_hash: BytesN<32>) -> Result<(), Error> {
+        admin::require_admin(&env)?;
+        env.deployer()
+            .update_current_contract_wasm(new_wasm_hash.clone());
+        events::upgraded(&env, &new_wasm_hash);
+        Ok(())
+    }
+
+    pub fn schema_version(env: Env) -> u32 {
+        schema_version(&env)
+    }
+
+    /// Rewrites up to `limit` missions (capped at `MAX_MIGRATION_BATCH`) into
+    /// the next schema version, resuming where the previous call stopped;
+    /// the step to version 5 moves up to `limit` list items instead (capped
+    /// at `MAX_LIST_MIGRATION_BATCH`). The version is bumped once the step
+    /// is complete. Returns `true` once the contract is on `SCHEMA_VERSION`;
+    /// until then keep calling it.
+    pub fn migrate(env: Env, limit: u32) -> Result<bool, Error> {
+        admin::require_admin(&env)?;
+        let version = schema_version(&env);
+        if version >= SCHEMA_VERSION {
+            return Ok(true);
+        }
+
+        let done = if version == 4 {
+            migrate_lists(&env, limit)
+        } else {
+            migrate_missions(&env, version, limit)?
+        };
+        if !done {
+            return Ok(false);
+        }
+        env.storage().instance().remove(&DataKey::MigrationCursor);
+        env.storage()
+            .instance()
+            .set(&DataKey::SchemaVersion, &(version + 1));
+        storage::bump_instance(&env);
+        events::schema_migrated(&env, version + 1);
+        Ok(version + 1 == SCHEMA_VERSION)
+    }
+}